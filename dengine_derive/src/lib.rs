@@ -9,7 +9,7 @@ extern crate quote;
 use proc_macro2::TokenStream;
 use syn::{DataStruct, DeriveInput};
 
-#[proc_macro_derive(Insertable)]
+#[proc_macro_derive(Insertable, attributes(dengine))]
 pub fn insertable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // Parse the input tokens into a syntax tree
     let input: DeriveInput = syn::parse(input).unwrap();
@@ -29,7 +29,7 @@ fn impl_insertable(ast: &DeriveInput) -> TokenStream {
     }
 
     quote!{
-        impl Insertable for #name {
+        impl ::dengine::Insertable for #name {
             #funs
         }
     }
@@ -38,29 +38,19 @@ fn impl_insertable(ast: &DeriveInput) -> TokenStream {
 fn gen_funs(data: &DataStruct) -> TokenStream {
     match data.fields {
         syn::Fields::Named(ref fnames) => {
-            let mut fields: Vec<String> = Vec::new();
+            let columns: Vec<&syn::Field> = fnames.named.iter().filter(|f| is_column(f)).collect();
 
-            for n in &fnames.named {
-                let b = &n.ident;
+            let fields = columns.iter().map(|f| colum_name(f));
 
-                match b {
-                    Some(x) => fields.push(x.to_string()),
-                    _ => panic!("cannot unwrap()"),
-                }
-            }
-
-            let fields = fields.iter();
-
-            let fields2 = fnames.named.iter().map(|f| &f.ident);
+            let fields2 = columns.iter().map(|f| &f.ident);
             quote!{
 
                     fn fields()->Vec<String>{
-                        //#fields
                         vec![#(#fields.to_string()),*]
                     }
 
-                    fn values(&self)-> Vec<Dypes>{
-                        vec![#(Dypes::from(self.#fields2.clone())),*]
+                    fn values(&self)-> Vec<::dengine::Dypes>{
+                        vec![#(::dengine::Dypes::from(self.#fields2.clone())),*]
                     }
 
             }
@@ -69,7 +59,7 @@ fn gen_funs(data: &DataStruct) -> TokenStream {
     }
 }
 
-#[proc_macro_derive(Queryable)]
+#[proc_macro_derive(Queryable, attributes(dengine))]
 pub fn queryable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // Parse the input tokens into a syntax tree
     let input: DeriveInput = syn::parse(input).unwrap();
@@ -82,33 +72,304 @@ pub fn queryable_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 fn impl_queryable(ast: &DeriveInput) -> TokenStream {
     let name = &ast.ident;
     let funs;
+    let has_many;
 
     match ast.data {
-        syn::Data::Struct(ref d) => funs = gen_queryable_funs(d),
+        syn::Data::Struct(ref d) => {
+            funs = gen_queryable_funs(d);
+            has_many = gen_has_many(name, d);
+        }
         _ => panic!("Not as struct"),
     }
 
     quote!{
-        impl Queryable for #name {
+        impl ::dengine::Queryable for #name {
             #funs
         }
+
+        #has_many
     }
 }
 
 fn gen_queryable_funs(data: &DataStruct) -> TokenStream {
     match data.fields {
         syn::Fields::Named(ref fnames) => {
-            let fields1 = fnames.named.iter().map(|f| &f.ident);
-            let fields2 = fnames.named.iter().map(|f| &f.ident);
+            let fields = fnames.named.iter().map(|f| {
+                let ident = &f.ident;
+                if str_attr(f, "has_many").is_some() || has_word_attr(f, "skip") {
+                    quote!{ #ident: Default::default() }
+                } else if has_word_attr(f, "flatten") {
+                    let prefix = str_attr(f, "prefix").unwrap_or_default();
+                    if is_option(&f.ty) {
+                        quote!{ #ident: row.nested_opt(#prefix)? }
+                    } else {
+                        quote!{ #ident: row.nested(#prefix)? }
+                    }
+                } else if has_word_attr(f, "lossy") {
+                    let colum = colum_name(f);
+                    quote!{ #ident: row.try_get_lossy(#colum)? }
+                } else {
+                    let colum = colum_name(f);
+                    quote!{ #ident: row.try_get(#colum)? }
+                }
+            });
             quote!{
 
-                fn new(row: Row) -> Self {
-                    Self {
-                        #(#fields1: row.get(stringify!(#fields2)).unwrap()),*
-                    }
+                fn new(row: ::dengine::Row) -> Self {
+                    Self::try_new(row).unwrap()
+                }
+
+                fn try_new(row: ::dengine::Row) -> ::dengine::Desult<Self> {
+                    Ok(Self {
+                        #(#fields),*
+                    })
                 }
             }
         }
         _ => panic!("Not named fields"),
     }
 }
+
+/// impl HasMany for fields with #[dengine(has_many = "table", fk = "colum")].
+/// key names the field of the parent matched against fk, defaults to id
+fn gen_has_many(name: &syn::Ident, data: &DataStruct) -> TokenStream {
+    let fnames = match data.fields {
+        syn::Fields::Named(ref fnames) => fnames,
+        _ => panic!("Not named fields"),
+    };
+
+    let loads: Vec<TokenStream> = fnames
+        .named
+        .iter()
+        .filter_map(|f| {
+            let table = str_attr(f, "has_many")?;
+            let ident = &f.ident;
+            let fk = str_attr(f, "fk").expect("has_many needs fk = \"colum\"");
+            let key = str_attr(f, "key").unwrap_or_else(|| "id".to_string());
+            let key = syn::Ident::new(&key, proc_macro2::Span::call_site());
+
+            Some(quote!{
+                {
+                    let keys: Vec<_> = parents.iter().map(|x| x.#key.clone()).collect();
+                    let mut children = con.children_by_key(#table, #fk, keys)?;
+                    for x in parents.iter_mut() {
+                        x.#ident = children.remove(&x.#key).unwrap_or_default();
+                    }
+                }
+            })
+        }).collect();
+
+    if loads.is_empty() {
+        return quote!{};
+    }
+
+    quote!{
+        impl ::dengine::HasMany for #name {
+            fn load_has_many<C>(parents: &mut [Self], con: &C) -> ::dengine::Desult<()>
+            where
+                C: ::dengine::Connectionable,
+            {
+                #(#loads)*
+                Ok(())
+            }
+        }
+    }
+}
+
+#[proc_macro_derive(Table, attributes(dengine))]
+pub fn table_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    // Parse the input tokens into a syntax tree
+    let input: DeriveInput = syn::parse(input).unwrap();
+
+    let extended = impl_table(&input);
+
+    extended.into()
+}
+
+fn impl_table(ast: &DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let table = attrs_str(&ast.attrs, "table").unwrap_or_else(|| snake_case(&name.to_string()));
+
+    let fnames = match ast.data {
+        syn::Data::Struct(DataStruct {
+            fields: syn::Fields::Named(ref fnames),
+            ..
+        }) => fnames,
+        _ => panic!("Not a struct with named fields"),
+    };
+
+    let mut columns = Vec::new();
+    let mut primary_key = Vec::new();
+    // Index name, colums and if unique, in order of the first colum
+    let mut indexes: Vec<(String, Vec<String>, bool)> = Vec::new();
+
+    for f in &fnames.named {
+        if !is_column(f) {
+            continue;
+        }
+
+        let colum = colum_name(f);
+        let ty = &f.ty;
+
+        let data_type = match str_attr(f, "sql_type") {
+            Some(x) => quote!{ #x.to_string() },
+            None => quote!{
+                dialect.sql_type(<#ty as ::dengine::FieldType>::sql_type()).to_string()
+            },
+        };
+        let nullable = if has_word_attr(f, "nullable") {
+            quote!{ true }
+        } else {
+            quote!{ <#ty as ::dengine::FieldType>::nullable() }
+        };
+        let default = match str_attr(f, "default") {
+            Some(x) => quote!{ Some(#x.to_string()) },
+            None => quote!{ None },
+        };
+        let auto_increment = has_word_attr(f, "auto_increment");
+
+        columns.push(quote!{
+            ::dengine::ColumnSchema {
+                name: #colum.to_string(),
+                data_type: #data_type,
+                nullable: #nullable,
+                default: #default,
+                auto_increment: #auto_increment,
+            }
+        });
+
+        if has_word_attr(f, "primary_key") {
+            primary_key.push(colum.clone());
+        }
+
+        for &(attr, unique, suffix) in &[("unique", true, "key"), ("index", false, "idx")] {
+            let index = if has_word_attr(f, attr) {
+                format!("{}_{}_{}", table, colum, suffix)
+            } else {
+                match str_attr(f, attr) {
+                    Some(x) => x,
+                    None => continue,
+                }
+            };
+
+            match indexes.iter().position(|x| x.0 == index) {
+                Some(n) => indexes[n].1.push(colum.clone()),
+                None => indexes.push((index, vec![colum.clone()], unique)),
+            }
+        }
+    }
+
+    let indexes = indexes.into_iter().map(|(index, colums, unique)| {
+        quote!{
+            ::dengine::IndexSchema {
+                name: #index.to_string(),
+                columns: vec![#(#colums.to_string()),*],
+                unique: #unique,
+            }
+        }
+    });
+
+    quote!{
+        impl ::dengine::Table for #name {
+            fn table_name() -> String {
+                #table.to_string()
+            }
+
+            fn table_schema(dialect: &dyn (::dengine::Dialect)) -> ::dengine::TableSchema {
+                ::dengine::TableSchema {
+                    schema: None,
+                    name: #table.to_string(),
+                    columns: vec![#(#columns),*],
+                    primary_key: vec![#(#primary_key.to_string()),*],
+                    indexes: vec![#(#indexes),*],
+                    foreign_keys: Vec::new(),
+                }
+            }
+        }
+    }
+}
+
+/// If a field maps to a colum, has_many, flatten and skip fields do not
+fn is_column(field: &syn::Field) -> bool {
+    !has_word_attr(field, "skip")
+        && !has_word_attr(field, "flatten")
+        && str_attr(field, "has_many").is_none()
+}
+
+/// Colum of a field, the field name unless renamed with #[dengine(rename = "colum")]
+fn colum_name(field: &syn::Field) -> String {
+    match str_attr(field, "rename") {
+        Some(x) => x,
+        None => field.ident.as_ref().expect("cannot unwrap()").to_string(),
+    }
+}
+
+/// UserAddress to user_address
+fn snake_case(name: &str) -> String {
+    let mut rt = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                rt.push('_');
+            }
+            rt.extend(c.to_lowercase());
+        } else {
+            rt.push(c);
+        }
+    }
+    rt
+}
+
+/// Items inside the #[dengine(...)] attributes of a field or struct
+fn dengine_attrs(attrs: &[syn::Attribute]) -> Vec<syn::Meta> {
+    let mut rt = Vec::new();
+    for attr in attrs.iter().filter_map(|a| a.interpret_meta()) {
+        if let syn::Meta::List(l) = attr {
+            if l.ident == "dengine" {
+                for n in l.nested {
+                    if let syn::NestedMeta::Meta(m) = n {
+                        rt.push(m);
+                    }
+                }
+            }
+        }
+    }
+    rt
+}
+
+/// Checks for #[dengine(word)] on a field
+fn has_word_attr(field: &syn::Field, word: &str) -> bool {
+    dengine_attrs(&field.attrs).iter().any(|m| match m {
+        syn::Meta::Word(ref w) => w == word,
+        _ => false,
+    })
+}
+
+/// Value of #[dengine(name = "value")] on a field
+fn str_attr(field: &syn::Field, name: &str) -> Option<String> {
+    attrs_str(&field.attrs, name)
+}
+
+/// Value of #[dengine(name = "value")] among attrs
+fn attrs_str(attrs: &[syn::Attribute], name: &str) -> Option<String> {
+    dengine_attrs(attrs)
+        .into_iter()
+        .filter_map(|m| match m {
+            syn::Meta::NameValue(ref nv) if nv.ident == name => match nv.lit {
+                syn::Lit::Str(ref s) => Some(s.value()),
+                _ => panic!("dengine attribute {} must be a string", name),
+            },
+            _ => None,
+        }).next()
+}
+
+/// Checks if the type is written as Option<T>
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(ref p) => match p.path.segments.iter().last() {
+            Some(x) => x.ident == "Option",
+            None => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,91 @@
+//! Writes Rust structs for the tables of an existing database.
+//!
+//! ```text
+//! dengine-codegen sqlite <file> [-o <dir>]
+//! dengine-codegen mysql <host> <user> <password> <database> [-o <dir>]
+//! ```
+//! Without -o a single module is written to stdout, with -o a file per table
+//! and a mod.rs are written to dir.
+
+extern crate dengine;
+
+use dengine::{codegen, my_sql, sqlite, Connectionable, Desult, Dialect, Error, Schema};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "Usage: dengine-codegen sqlite <file> [-o <dir>]
+       dengine-codegen mysql <host> <user> <password> <database> [-o <dir>]";
+
+fn main() {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => (),
+        Err(Error::LibErr(x)) => {
+            eprintln!("{}", x);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run(mut args: Vec<String>) -> Desult<()> {
+    let out = match args.iter().position(|x| x == "-o") {
+        Some(n) if n + 1 < args.len() => {
+            let dir = args.remove(n + 1);
+            args.remove(n);
+            Some(dir)
+        }
+        Some(_) => return Err(usage()),
+        None => None,
+    };
+
+    let (schema, dialect) = connect(&args)?;
+
+    match out {
+        Some(dir) => write_dir(&schema, dialect, Path::new(&dir)),
+        None => {
+            print!("{}", codegen::module(&schema, dialect));
+            Ok(())
+        }
+    }
+}
+
+fn connect(args: &[String]) -> Desult<(Schema, &'static dyn Dialect)> {
+    match args.first().map(|x| x.as_str()) {
+        Some("sqlite") if args.len() == 2 => {
+            let con = sqlite::Connection::new(&args[1])?;
+            Ok((con.schema()?, sqlite::Connection::dialect()))
+        }
+        Some("mysql") if args.len() == 5 => {
+            let con = my_sql::Connection::new(
+                args[1].clone(),
+                args[2].clone(),
+                args[3].clone(),
+                args[4].clone(),
+            );
+            Ok((con.schema()?, my_sql::Connection::dialect()))
+        }
+        _ => Err(usage()),
+    }
+}
+
+fn write_dir(schema: &Schema, dialect: &dyn Dialect, dir: &Path) -> Desult<()> {
+    fs::create_dir_all(dir).map_err(|e| Error::LibErr(e.to_string()))?;
+
+    for n in codegen::tables(schema) {
+        let path = dir.join(format!("{}.rs", codegen::module_name(n)));
+        fs::write(&path, codegen::table_module(n, dialect))
+            .map_err(|e| Error::LibErr(e.to_string()))?;
+    }
+
+    fs::write(dir.join("mod.rs"), codegen::mod_file(schema))
+        .map_err(|e| Error::LibErr(e.to_string()))
+}
+
+fn usage() -> Error {
+    Error::LibErr(USAGE.to_string())
+}
@@ -0,0 +1,546 @@
+//! Finds the placeholders of a query so params can be rewritten
+//! before they are sent to the backend.
+
+use super::{Desult, Dialect, Dypes, Error};
+use std::collections::HashMap;
+
+/// Build named params for any query function.
+/// `:name` is always a param, `@name` only when it is given so mysql
+/// user variables keep working. A name can be used more than once.
+/// ```ignore
+/// //Untested
+/// let users: Vec<User> = con
+///     .array(
+///         "SELECT * FROM user WHERE id = :id OR (name = :name AND parent_id = :id)",
+///         named_params!{ "id" => 1, "name" => "x" },
+///         false,
+///     ).unwrap();
+/// ```
+#[macro_export]
+macro_rules! named_params {
+    ($($name:expr => $val:expr),* $(,)*) => {{
+        let mut map = ::std::collections::HashMap::<String, $crate::Dypes>::new();
+        $(map.insert($name.to_string(), $crate::Dypes::from($val));)*
+        map
+    }};
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Placeholder<'a> {
+    /// ?
+    Positional,
+    /// ?NNN of sqlite, holds NNN
+    Numbered(usize),
+    /// :name, @name or $name of sqlite, holds the prefix and the name
+    Named(char, &'a str),
+}
+
+/// Split sql at its placeholders, skipping string literals, quoted identifiers and comments.
+/// Returns the sql between the placeholders, one more piece than placeholders
+pub(crate) fn split<'a>(
+    sql: &'a str,
+    dialect: &dyn Dialect,
+) -> (Vec<&'a str>, Vec<Placeholder<'a>>) {
+    let bytes = sql.as_bytes();
+    let mut pieces = Vec::new();
+    let mut holders = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(end) = skip_quoted(bytes, i, dialect) {
+            i = end;
+            continue;
+        }
+
+        match bytes[i] {
+            b'?' if is_digit(bytes.get(i + 1)) => {
+                let end = digits_end(bytes, i + 1);
+                pieces.push(&sql[start..i]);
+                holders.push(Placeholder::Numbered(sql[i + 1..end].parse().unwrap_or(0)));
+                // The number stays in the next piece
+                start = i;
+                i = end;
+            }
+            b'?' => {
+                pieces.push(&sql[start..i]);
+                holders.push(Placeholder::Positional);
+                i += 1;
+                start = i;
+            }
+            p @ b':' | p @ b'@' | p @ b'$'
+                if (p != b'$' || dialect.named_placeholders())
+                    && is_name_start(bytes.get(i + 1))
+                    && !is_name_byte(prev(bytes, i)) =>
+            {
+                let end = name_end(bytes, i + 1);
+                pieces.push(&sql[start..i]);
+                holders.push(Placeholder::Named(p as char, &sql[i + 1..end]));
+                i = end;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+
+    pieces.push(&sql[start..]);
+    (pieces, holders)
+}
+
+/// Split a script into its statements at ; outside string literals and comments.
+/// A ; inside BEGIN ... END of a CREATE statement, like the body of a trigger, does not
+/// split it. Empty statements are left out
+pub fn split_statements<'a>(sql: &'a str, dialect: &dyn Dialect) -> Vec<&'a str> {
+    let bytes = sql.as_bytes();
+    let mut rt = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    // Open BEGIN and CASE blocks, and if the statement is a CREATE that can hold them
+    let mut depth = 0;
+    let mut create = None;
+
+    while i < bytes.len() {
+        if let Some(end) = skip_quoted(bytes, i, dialect) {
+            i = end;
+            continue;
+        }
+
+        if bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' {
+            let end = name_end(bytes, i);
+            let word = sql[i..end].to_uppercase();
+            i = end;
+
+            if create.is_none() {
+                create = Some(word == "CREATE");
+            } else if create == Some(true) {
+                match word.as_str() {
+                    "BEGIN" | "CASE" => depth += 1,
+                    "END" => {
+                        let (next, next_end) = next_word(sql, i);
+                        match next.as_str() {
+                            // Closed blocks that were not counted
+                            "IF" | "LOOP" | "WHILE" | "REPEAT" => (),
+                            "CASE" => {
+                                depth -= 1;
+                                i = next_end;
+                            }
+                            _ => depth -= 1,
+                        }
+                        depth = depth.max(0);
+                    }
+                    _ => (),
+                }
+            }
+            continue;
+        }
+
+        if bytes[i] == b';' && depth == 0 {
+            rt.push(&sql[start..i]);
+            start = i + 1;
+            create = None;
+        }
+        i += 1;
+    }
+
+    rt.push(&sql[start..]);
+    rt.into_iter()
+        .filter(|x| !is_blank(x, dialect))
+        .map(|x| x.trim())
+        .collect()
+}
+
+/// Uppercased word after whitespace from i and its end
+fn next_word(sql: &str, mut i: usize) -> (String, usize) {
+    let bytes = sql.as_bytes();
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    let end = name_end(bytes, i);
+    (sql[i..end].to_uppercase(), end)
+}
+
+/// Checks if sql holds only whitespace and comments
+fn is_blank(sql: &str, dialect: &dyn Dialect) -> bool {
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match skip_quoted(bytes, i, dialect) {
+            Some(end) if bytes[i] == b'-' || bytes[i] == b'/' || bytes[i] == b'#' => i = end,
+            Some(_) => return false,
+            None if bytes[i].is_ascii_whitespace() => i += 1,
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// End of the string literal, quoted identifier or comment starting at i.
+/// Mysql, the dialect with backslash escapes, also has # comments and needs
+/// whitespace after --
+fn skip_quoted(bytes: &[u8], mut i: usize, dialect: &dyn Dialect) -> Option<usize> {
+    match bytes[i] {
+        q @ b'\'' | q @ b'"' | q @ b'`' => {
+            i += 1;
+            while i < bytes.len() {
+                if bytes[i] == b'\\' && q != b'`' && dialect.backslash_escapes() {
+                    i += 2;
+                } else if bytes[i] == q {
+                    // A doubled quote is an escaped quote
+                    if bytes.get(i + 1) == Some(&q) {
+                        i += 2;
+                    } else {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            Some(i + 1)
+        }
+        b'-' if bytes.get(i + 1) == Some(&b'-')
+            && (!dialect.backslash_escapes()
+                || is_space_or_end(bytes.get(i + 2))) =>
+        {
+            Some(line_end(bytes, i))
+        }
+        b'#' if dialect.backslash_escapes() => Some(line_end(bytes, i)),
+        b'/' if bytes.get(i + 1) == Some(&b'*') => {
+            i += 2;
+            while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                i += 1;
+            }
+            Some(i + 2)
+        }
+        _ => None,
+    }
+}
+
+/// Check the number of ? placeholders against params and expand list values.
+/// :name and ?NNN placeholders are left as they are, sql with ?NNN is not checked
+pub(crate) fn bind_positional(
+    sql: &str,
+    dialect: &dyn Dialect,
+    params: Vec<Dypes>,
+) -> Desult<(String, Vec<Dypes>)> {
+    let (pieces, holders) = split(sql, dialect);
+
+    let expected = param_count(&holders, dialect);
+    if expected != params.len() {
+        return Err(Error::LibErr(format!(
+            "Query expects {} params, got {}: {}",
+            expected,
+            params.len(),
+            sql
+        )));
+    }
+
+    if !params.iter().any(is_list) {
+        return Ok((sql.to_string(), params));
+    }
+
+    // Expanding a list would change the numbers the backend gives the params after it
+    if holders.iter().any(|x| *x != Placeholder::Positional && is_param(x, dialect)) {
+        return Err(Error::LibErr(
+            "Lists can only be bound to queries with ? placeholders alone".to_string(),
+        ));
+    }
+
+    let mut rt = pieces[0].to_string();
+    let mut values = Vec::new();
+    let mut params = params.into_iter();
+
+    for (n, piece) in holders.iter().zip(pieces[1..].iter()) {
+        match *n {
+            Placeholder::Positional => {
+                if let Some(x) = params.next() {
+                    push_value(&mut rt, &mut values, x, dialect);
+                }
+            }
+            Placeholder::Named(p, name) => {
+                rt.push(p);
+                rt += name;
+            }
+            Placeholder::Numbered(_) => (),
+        }
+        rt += piece;
+    }
+
+    Ok((rt, values))
+}
+
+/// Number of params the backend binds, numbered as sqlite does: ? takes the number after
+/// the largest so far, ?NNN takes NNN and a name takes the next number when first used
+fn param_count(holders: &[Placeholder], dialect: &dyn Dialect) -> usize {
+    let mut count = 0;
+    let mut names = Vec::new();
+
+    for n in holders.iter().filter(|x| is_param(x, dialect)) {
+        match *n {
+            Placeholder::Positional => count += 1,
+            Placeholder::Numbered(x) => count = std::cmp::max(count, x),
+            Placeholder::Named(p, name) => {
+                if !names.contains(&(p, name)) {
+                    names.push((p, name));
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    count
+}
+
+/// Names are only params of dialects with named placeholders,
+/// for mysql they are user variables
+fn is_param(holder: &Placeholder, dialect: &dyn Dialect) -> bool {
+    match *holder {
+        Placeholder::Named(..) => dialect.named_placeholders(),
+        _ => true,
+    }
+}
+
+/// Rewrite the named placeholders of sql to ? and order the values to match
+pub(crate) fn bind_named(
+    sql: &str,
+    dialect: &dyn Dialect,
+    params: &HashMap<String, Dypes>,
+) -> Desult<(String, Vec<Dypes>)> {
+    let (pieces, holders) = split(sql, dialect);
+    let mut rt = pieces[0].to_string();
+    let mut values = Vec::new();
+
+    for (n, piece) in holders.iter().zip(pieces[1..].iter()) {
+        match *n {
+            Placeholder::Positional | Placeholder::Numbered(_) => {
+                return Err(Error::LibErr(
+                    "Positional ? placeholders cannot be mixed with named params".to_string(),
+                ))
+            }
+            Placeholder::Named(p, name) => match params.get(name) {
+                Some(x) => push_value(&mut rt, &mut values, x.clone(), dialect),
+                // Not a param, ex: a mysql user variable
+                None if p == '@' => {
+                    rt.push(p);
+                    rt += name;
+                }
+                None => return Err(Error::LibErr(format!("No value for named param :{}", name))),
+            },
+        }
+        rt += piece;
+    }
+
+    Ok((rt, values))
+}
+
+/// Add the placeholder for val to sql, a list gets one placeholder per value
+fn push_value(sql: &mut String, values: &mut Vec<Dypes>, val: Dypes, dialect: &dyn Dialect) {
+    match val {
+        Dypes::List(ref x) if x.is_empty() => *sql += dialect.empty_list(),
+        Dypes::List(x) => {
+            *sql += &dialect.placeholders(x.len());
+            values.extend(x);
+        }
+        x => {
+            *sql += dialect.placeholder();
+            values.push(x);
+        }
+    }
+}
+
+fn is_list(val: &Dypes) -> bool {
+    match val {
+        Dypes::List(_) => true,
+        _ => false,
+    }
+}
+
+fn prev(bytes: &[u8], i: usize) -> Option<&u8> {
+    if i == 0 {
+        None
+    } else {
+        bytes.get(i - 1)
+    }
+}
+
+/// End of the line comment starting at i
+fn line_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i] != b'\n' {
+        i += 1;
+    }
+    i
+}
+
+fn is_space_or_end(b: Option<&u8>) -> bool {
+    match b {
+        Some(b) => b.is_ascii_whitespace(),
+        None => true,
+    }
+}
+
+fn is_digit(b: Option<&u8>) -> bool {
+    match b {
+        Some(b) => b.is_ascii_digit(),
+        None => false,
+    }
+}
+
+fn digits_end(bytes: &[u8], mut i: usize) -> usize {
+    while is_digit(bytes.get(i)) {
+        i += 1;
+    }
+    i
+}
+
+fn is_name_start(b: Option<&u8>) -> bool {
+    match b {
+        Some(b) => b.is_ascii_alphabetic() || *b == b'_',
+        None => false,
+    }
+}
+
+fn is_name_byte(b: Option<&u8>) -> bool {
+    match b {
+        Some(b) => b.is_ascii_alphanumeric() || *b == b'_' || *b == b':' || *b == b'@',
+        None => false,
+    }
+}
+
+fn name_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {MysqlDialect, SqliteDialect};
+
+    #[test]
+    fn split_finds_placeholders() {
+        let (pieces, holders) = split(
+            "SELECT ?, :name, @var FROM t WHERE a = '?:x' AND b = ?",
+            &MysqlDialect,
+        );
+
+        assert_eq!(
+            holders,
+            vec![
+                Placeholder::Positional,
+                Placeholder::Named(':', "name"),
+                Placeholder::Named('@', "var"),
+                Placeholder::Positional,
+            ]
+        );
+        assert_eq!(pieces.len(), holders.len() + 1);
+        assert_eq!(pieces[0], "SELECT ");
+        assert_eq!(pieces[4], "");
+    }
+
+    #[test]
+    fn split_skips_comments_and_names() {
+        let (_, holders) = split("SELECT a:b, x -- ?\n FROM t /* :c */", &SqliteDialect);
+        assert!(holders.is_empty());
+
+        let (_, holders) = split("SELECT ?1, ?", &SqliteDialect);
+        assert_eq!(holders, vec![Placeholder::Numbered(1), Placeholder::Positional]);
+    }
+
+    fn ints(n: i64) -> Vec<Dypes> {
+        (1..n + 1).map(Dypes::Int).collect()
+    }
+
+    #[test]
+    fn bind_counts_numbered_and_named() {
+        // ?NNN counts by its highest number, a name once however often it is used
+        assert!(bind_positional("SELECT ?2, ?1", &SqliteDialect, ints(2)).is_ok());
+        assert!(bind_positional("SELECT ?3", &SqliteDialect, ints(1)).is_err());
+        assert!(bind_positional("SELECT :a, ?, :a, $b", &SqliteDialect, ints(3)).is_ok());
+        assert!(bind_positional("SELECT :a, ?", &SqliteDialect, ints(1)).is_err());
+
+        // mysql names are user variables
+        assert!(bind_positional("SELECT @a := ?", &MysqlDialect, ints(1)).is_ok());
+    }
+
+    #[test]
+    fn bind_rejects_lists_beside_numbered_and_named() {
+        let params = vec![Dypes::List(ints(2)), Dypes::Int(3)];
+        let sql = "SELECT * FROM t WHERE a IN (?) AND b = ?";
+        let (sql, values) = bind_positional(sql, &SqliteDialect, params.clone()).unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE a IN (?,?) AND b = ?");
+        assert_eq!(values.len(), 3);
+
+        let sql = "SELECT * FROM t WHERE a IN (?) AND b = :b";
+        assert!(bind_positional(sql, &SqliteDialect, params.clone()).is_err());
+
+        let sql = "SELECT * FROM t WHERE a IN (?1) AND b = ?2";
+        assert!(bind_positional(sql, &SqliteDialect, params).is_err());
+    }
+
+    #[test]
+    fn split_statements_at_semicolons() {
+        let sql = "CREATE TABLE a (x TEXT DEFAULT ';');\n\n\
+                   -- comment;\nINSERT INTO a VALUES ('b;c');;";
+        assert_eq!(
+            split_statements(sql, &SqliteDialect),
+            vec![
+                "CREATE TABLE a (x TEXT DEFAULT ';')",
+                "-- comment;\nINSERT INTO a VALUES ('b;c')",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_statements_keeps_trigger_bodies() {
+        let sql = "CREATE TRIGGER t AFTER INSERT ON a BEGIN
+                UPDATE a SET x = CASE WHEN x > 1 THEN 1 ELSE 2 END;
+                DELETE FROM b;
+            END;
+            BEGIN;
+            COMMIT;";
+        let statements = split_statements(sql, &SqliteDialect);
+
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("END"));
+        assert_eq!(statements[1], "BEGIN");
+        assert_eq!(statements[2], "COMMIT");
+    }
+
+    #[test]
+    fn split_statements_mysql_blocks() {
+        let sql = "CREATE PROCEDURE p() BEGIN IF 1 THEN SELECT 1; END IF; END; SELECT 2";
+        assert_eq!(
+            split_statements(sql, &MysqlDialect),
+            vec!["CREATE PROCEDURE p() BEGIN IF 1 THEN SELECT 1; END IF; END", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn skip_quoted_strings() {
+        let sql = b"'it''s' x";
+        assert_eq!(skip_quoted(sql, 0, &SqliteDialect), Some(7));
+
+        let sql = br"'a\'b' x";
+        assert_eq!(skip_quoted(sql, 0, &MysqlDialect), Some(6));
+        assert_eq!(skip_quoted(sql, 0, &SqliteDialect), Some(4));
+
+        assert_eq!(skip_quoted(b"`a``b` x", 0, &MysqlDialect), Some(6));
+        assert_eq!(skip_quoted(b"x", 0, &MysqlDialect), None);
+    }
+
+    #[test]
+    fn skip_quoted_comments() {
+        assert_eq!(skip_quoted(b"/* a */ x", 0, &SqliteDialect), Some(7));
+        assert_eq!(skip_quoted(b"-- a\nx", 0, &SqliteDialect), Some(4));
+        assert_eq!(skip_quoted(b"--a\nx", 0, &SqliteDialect), Some(3));
+
+        // mysql needs whitespace after -- and has # comments
+        assert_eq!(skip_quoted(b"--a\nx", 0, &MysqlDialect), None);
+        assert_eq!(skip_quoted(b"-- a\nx", 0, &MysqlDialect), Some(4));
+        assert_eq!(skip_quoted(b"# a\nx", 0, &MysqlDialect), Some(3));
+        assert_eq!(skip_quoted(b"# a\nx", 0, &SqliteDialect), None);
+    }
+}
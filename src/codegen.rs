@@ -0,0 +1,208 @@
+//! Rust structs for the tables of an existing database, used by the dengine-codegen binary.
+//! Each table gets a struct deriving Queryable and Insertable, with Option fields for
+//! nullable colums and #[dengine(rename = "colum")] where a colum is not a valid field name.
+//! Colums that also fail validate_ident, like "First Name", can be selected but not inserted.
+//! ```ignore
+//! //Untested
+//! let schema = con.schema().unwrap();
+//! std::fs::write("src/models.rs", codegen::module(&schema, DbCon::dialect())).unwrap();
+//! ```
+
+use super::migrations::MIGRATIONS_TABLE;
+use super::{ColumnSchema, Dialect, Schema, SqlType, TableSchema};
+
+const HEADER: &str = "//! Generated by dengine-codegen, do not edit.
+//! Needs extern crate dengine and #[macro_use] extern crate dengine_derive in the crate root
+";
+
+const KEYWORDS: [&str; 52] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield", "union",
+];
+
+/// Names used by the generated code that a struct may not shadow
+const TYPES: [&str; 7] = ["Dypes", "Insertable", "Option", "Queryable", "Row", "String", "Vec"];
+
+/// Module with a struct per table, the migrations table is left out
+pub fn module(schema: &Schema, dialect: &dyn Dialect) -> String {
+    let mut rt = HEADER.to_string();
+    for n in tables(schema) {
+        rt += "\n";
+        rt += &table_struct(n, dialect);
+    }
+    rt
+}
+
+/// Module with the struct of a single table
+pub fn table_module(table: &TableSchema, dialect: &dyn Dialect) -> String {
+    format!("{}\n{}", HEADER, table_struct(table, dialect))
+}
+
+/// mod.rs for a file per table named by module_name
+pub fn mod_file(schema: &Schema) -> String {
+    let mut rt = "//! Generated by dengine-codegen, do not edit.\n\n".to_string();
+    for n in tables(schema) {
+        rt += &format!("pub mod {};\n", module_name(n));
+    }
+    rt += "\n";
+    for n in tables(schema) {
+        rt += &format!("pub use self::{}::{};\n", module_name(n), struct_name(&n.name));
+    }
+    rt
+}
+
+/// Module name of a table, ex: "UserAddress" to user_address
+pub fn module_name(table: &TableSchema) -> String {
+    field_name(&table.name)
+}
+
+/// Struct for a table
+pub fn table_struct(table: &TableSchema, dialect: &dyn Dialect) -> String {
+    let mut rt = format!(
+        "/// Row of the {} table\n\
+         #[derive(Clone, Debug, Queryable, Insertable)]\n\
+         pub struct {} {{\n",
+        table.qualified_name(),
+        struct_name(&table.name)
+    );
+
+    let mut fields: Vec<String> = Vec::new();
+    for n in &table.columns {
+        let mut field = field_name(&n.name);
+        // Colums like "a b" and "a_b" end up with the same name
+        if fields.contains(&field) {
+            let mut i = 2;
+            while fields.contains(&format!("{}_{}", field, i)) {
+                i += 1;
+            }
+            field = format!("{}_{}", field, i);
+        }
+
+        if field != n.name {
+            rt += &format!("    #[dengine(rename = {:?})]\n", n.name);
+        }
+        rt += &format!("    pub {}: {},\n", field, rust_type(n, dialect));
+        fields.push(field);
+    }
+
+    rt += "}\n";
+    rt
+}
+
+/// Rust type of a colum, Option<T> when nullable.
+/// Dypes for types without a known mapping
+pub fn rust_type(colum: &ColumnSchema, dialect: &dyn Dialect) -> String {
+    // sqlite integers are 64 bit whatever the declared type
+    let sqlite = dialect.name() == "sqlite";
+
+    let rt = match SqlType::from_decl(&colum.data_type) {
+        Some(SqlType::Bool) => "bool",
+        Some(SqlType::Int) if sqlite => "i64",
+        Some(SqlType::Int) => "i32",
+        Some(SqlType::Uint) if sqlite => "u64",
+        Some(SqlType::Uint) => "u32",
+        Some(SqlType::BigInt) => "i64",
+        Some(SqlType::BigUint) => "u64",
+        Some(SqlType::Float) | Some(SqlType::Double) => "f64",
+        // mysql returns decimals as text
+        Some(SqlType::Decimal) if sqlite => "f64",
+        // mysql also returns dates and times as text
+        Some(SqlType::Decimal) | Some(SqlType::Date) | Some(SqlType::Text) => "String",
+        Some(SqlType::Bytes) => "Vec<u8>",
+        None => "Dypes",
+    };
+
+    if colum.nullable {
+        format!("Option<{}>", rt)
+    } else {
+        rt.to_string()
+    }
+}
+
+/// Struct name of a table, ex: "user_address" to UserAddress
+pub fn struct_name(table: &str) -> String {
+    let mut rt: String = field_name(table)
+        .split('_')
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let mut chars = x.chars();
+            match chars.next() {
+                Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        }).collect();
+
+    if rt.is_empty() || rt.starts_with(|c: char| c.is_ascii_digit()) {
+        rt = format!("T{}", rt);
+    }
+    if KEYWORDS.contains(&rt.as_str()) || TYPES.contains(&rt.as_str()) {
+        rt.push('_');
+    }
+    rt
+}
+
+/// Snake case field name of a colum, ex: "First Name" or "firstName" to first_name.
+/// Keywords get a trailing _
+pub fn field_name(colum: &str) -> String {
+    let mut rt = String::new();
+    let mut prev_lower = false;
+
+    for c in colum.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                rt.push('_');
+            }
+            rt.push(c.to_ascii_lowercase());
+            prev_lower = !c.is_ascii_uppercase();
+        } else {
+            if !rt.is_empty() && !rt.ends_with('_') {
+                rt.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+
+    let mut rt = rt.trim_end_matches('_').to_string();
+    if rt.is_empty() {
+        rt = "colum".to_string();
+    } else if rt.starts_with(|c: char| c.is_ascii_digit()) {
+        rt = format!("_{}", rt);
+    }
+    if KEYWORDS.contains(&rt.as_str()) {
+        rt.push('_');
+    }
+    rt
+}
+
+/// Tables structs are generated for, all but the migrations table
+pub fn tables(schema: &Schema) -> impl Iterator<Item = &TableSchema> {
+    schema.tables.iter().filter(|x| x.name != MIGRATIONS_TABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_names() {
+        assert_eq!(field_name("user_name"), "user_name");
+        assert_eq!(field_name("First Name"), "first_name");
+        assert_eq!(field_name("firstName"), "first_name");
+        assert_eq!(field_name("ID"), "id");
+        assert_eq!(field_name("2fa"), "_2fa");
+        assert_eq!(field_name("type"), "type_");
+        assert_eq!(field_name("--"), "colum");
+    }
+
+    #[test]
+    fn struct_names() {
+        assert_eq!(struct_name("user_address"), "UserAddress");
+        assert_eq!(struct_name("user"), "User");
+        assert_eq!(struct_name("2019_sales"), "T2019Sales");
+        assert_eq!(struct_name("row"), "Row_");
+        assert_eq!(struct_name("self"), "Self_");
+    }
+}
@@ -0,0 +1,209 @@
+//! Copy tables between connections of either backend, see copy_table.
+//! ```ignore
+//! //Untested
+//! let options = CopyOptions {
+//!     watermark: Some("updated_at".to_string()),
+//!     ..Default::default()
+//! };
+//!
+//! // The first run copies all rows, later runs the rows updated since
+//! let report = copy_table(&mysql_con, &sqlite_con, "user", &options).unwrap();
+//! println!("{} rows copied", report.written);
+//! ```
+
+use super::dump::{portable_table, text_bytes};
+use super::{ColumnSchema, Connectionable, Desult, DynRow, Dypes, Error, Params, TableSchema};
+use std;
+
+/// Options of copy_table
+#[derive(Clone, Debug)]
+pub struct CopyOptions {
+    /// Name of the destination table, the source name when None
+    pub dst_table: Option<String>,
+    /// Colum like updated_at or an id that grows as rows change. Only rows from the
+    /// highest value in the destination table on are copied, and are upserted by primary key
+    pub watermark: Option<String>,
+    /// Rows per INSERT, defaults to 500
+    pub chunk_size: usize,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            dst_table: None,
+            watermark: None,
+            chunk_size: 500,
+        }
+    }
+}
+
+/// Result of copy_table
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CopyReport {
+    /// If the destination table was created
+    pub created: bool,
+    /// Rows read from the source
+    pub read: u64,
+    /// Rows inserted or updated in the destination
+    pub written: u64,
+    /// Highest watermark copied, None without a watermark or rows
+    pub watermark: Option<Dypes>,
+}
+
+/// Sqlite before 3.32 takes at most 999 params per statement
+const MAX_PARAMS: usize = 999;
+
+/// Copy the rows of table from src to dst in chunks.
+/// A missing destination table is created with the schema of table translated for
+/// the backend of dst, without foreign keys so tables can be copied in any order.
+/// With a watermark rows with a watermark equal to or above the highest one in dst are
+/// copied again and replace the rows with the same primary key, without a primary key
+/// only rows above it are copied
+pub fn copy_table<S, D>(src: &S, dst: &D, table: &str, options: &CopyOptions) -> Desult<CopyReport>
+where
+    S: Connectionable,
+    D: Connectionable,
+{
+    let src_schema = src.schema()?;
+    let source = match src_schema.table(table) {
+        Some(x) => x,
+        None => return Err(Error::LibErr(format!("No table {}", table))),
+    };
+    let dst_name = options.dst_table.clone().unwrap_or_else(|| source.name.clone());
+
+    let mut report = CopyReport::default();
+    let dst_schema = dst.schema()?;
+    let target = match dst_schema.table(&dst_name) {
+        Some(x) => x.clone(),
+        None => {
+            let mut x = portable_table(source, S::dialect(), D::dialect());
+            x.name = dst_name.clone();
+            x.foreign_keys.clear();
+            for sql in D::dialect().create_table_sql(&x)? {
+                dst.execute(&sql, ())?;
+            }
+            report.created = true;
+            x
+        }
+    };
+
+    // Colums of the source the destination also has
+    let src_columns: Vec<&ColumnSchema> = source
+        .columns
+        .iter()
+        .filter(|x| target.column(&x.name).is_some())
+        .collect();
+    let columns: Vec<String> = src_columns.iter().map(|x| x.name.clone()).collect();
+    if columns.is_empty() {
+        return Err(Error::LibErr(format!("No colums of {} in {}", table, dst_name)));
+    }
+
+    let upsert = !target.primary_key.is_empty()
+        && target.primary_key.iter().all(|x| columns.contains(x));
+
+    let mut sql = format!(
+        "SELECT {} FROM {}",
+        columns
+            .iter()
+            .map(|x| S::quote_ident(x))
+            .collect::<Vec<String>>()
+            .join(", "),
+        S::dialect().ident(&source.qualified_name())?
+    );
+    let mut params = Vec::new();
+    let mut watermark_index = None;
+
+    if let Some(ref wm) = options.watermark {
+        watermark_index = Some(columns.iter().position(|x| x == wm).ok_or_else(|| {
+            Error::LibErr(format!("No watermark colum {} in {} and {}", wm, table, dst_name))
+        })?);
+
+        if let Some(from) = max_value(dst, &target, wm)? {
+            sql += &format!(
+                " WHERE {} {} {}",
+                S::dialect().ident(wm)?,
+                if upsert { ">=" } else { ">" },
+                S::dialect().placeholder()
+            );
+            params.push(from);
+        }
+        sql += &format!(" ORDER BY {}", S::dialect().ident(wm)?);
+    }
+
+    let chunk_size = options.chunk_size.max(1).min(MAX_PARAMS / columns.len()).max(1);
+    let mut chunk: Vec<Dypes> = Vec::new();
+    let mut rows = 0;
+
+    src.for_each_row(&sql, Params::new(params), |row: DynRow| {
+        for (val, colum) in row.iter().zip(src_columns.iter()) {
+            chunk.push(text_bytes(&val.1, colum));
+        }
+        if let Some(i) = watermark_index {
+            report.watermark = row.get(i).map(text_value);
+        }
+        report.read += 1;
+        rows += 1;
+
+        if rows >= chunk_size {
+            write_chunk(dst, &target, &columns, &mut chunk, rows, upsert)?;
+            report.written += rows as u64;
+            rows = 0;
+        }
+        Ok(())
+    })?;
+
+    if rows > 0 {
+        write_chunk(dst, &target, &columns, &mut chunk, rows, upsert)?;
+        report.written += rows as u64;
+    }
+
+    Ok(report)
+}
+
+fn write_chunk<D: Connectionable>(
+    dst: &D,
+    table: &TableSchema,
+    columns: &[String],
+    chunk: &mut Vec<Dypes>,
+    rows: usize,
+    upsert: bool,
+) -> Desult<()> {
+    let name = table.qualified_name();
+    let sql = if upsert {
+        D::dialect().upsert_sql(&name, columns, &table.primary_key, rows)?
+    } else {
+        D::dialect().insert_sql(&name, columns, rows)?
+    };
+
+    dst.execute(&sql, Params::new(chunk.split_off(0)))
+}
+
+/// Highest value of colum in table, None when it is empty
+fn max_value<D: Connectionable>(
+    dst: &D,
+    table: &TableSchema,
+    colum: &str,
+) -> Desult<Option<Dypes>> {
+    let sql = format!(
+        "SELECT MAX({}) AS max_value FROM {}",
+        D::dialect().ident(colum)?,
+        D::dialect().ident(&table.qualified_name())?
+    );
+    let rows: Vec<DynRow> = dst.array(&sql, (), false)?;
+
+    Ok(match rows.first().and_then(|x| x.get(0)) {
+        Some(Dypes::Null) | None => None,
+        Some(x) => Some(text_value(x)),
+    })
+}
+
+/// Mysql returns dates and text as bytes, as a string to be bound on either backend
+fn text_value(val: &Dypes) -> Dypes {
+    match val {
+        Dypes::Bytes(x) => match std::str::from_utf8(x) {
+            Ok(s) => Dypes::String(s.to_string()),
+            Err(_) => val.clone(),
+        },
+        x => x.clone(),
+    }
+}
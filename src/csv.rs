@@ -0,0 +1,558 @@
+//! CSV import and export, see Connectionable::export_csv and Connectionable::import_csv.
+//! An empty unquoted field is NULL and a quoted empty field "" is an empty string,
+//! bytes of blob and binary colums are base64, so exported files import back the same.
+//! ```ignore
+//! //Untested
+//! let file = std::fs::File::create("user.csv").unwrap();
+//! let options = CsvOptions {
+//!     skip_errors: true,
+//!     ..Default::default()
+//! };
+//! con.export_csv("SELECT * FROM user WHERE active = ?", 1, file, &options).unwrap();
+//!
+//! let file = std::fs::File::open("user.csv").unwrap();
+//! let report = con.import_csv("user", file, &options).unwrap();
+//!
+//! for n in &report.errors {
+//!     println!("line {}: {}", n.line, n.message);
+//! }
+//! ```
+
+use super::{
+    base64, ColumnMeta, ColumnSchema, Connectionable, Desult, DynRow, Dypes, Error, Params,
+    Queryable, Row, SqlType, TableSchema,
+};
+use std;
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Options of Connectionable::import_csv, export_csv only uses the delimiter
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    /// Field separator, defaults to ,
+    pub delimiter: char,
+    /// Unquoted field read as NULL, defaults to the empty field. None reads no NULLs
+    pub null: Option<String>,
+    /// Rows per INSERT, defaults to 500
+    pub chunk_size: usize,
+    /// Report lines that fail to convert or insert and go on, instead of stopping at the first
+    pub skip_errors: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            null: Some(String::new()),
+            chunk_size: 500,
+            skip_errors: false,
+        }
+    }
+}
+
+/// Result of Connectionable::import_csv and Connectionable::import_jsonl
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub inserted: u64,
+    /// Skipped lines, only with skip_errors set
+    pub errors: Vec<LineError>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LineError {
+    /// Line the record starts on, the first line is 1
+    pub line: usize,
+    pub message: String,
+}
+
+/// A csv record without line break, None fields are NULL and written as empty fields.
+/// Fields holding the delimiter, a quote or line break and empty strings are quoted
+pub fn csv_record<'a, I>(fields: I, delimiter: char) -> String
+where
+    I: IntoIterator<Item = Option<&'a str>>,
+{
+    fields
+        .into_iter()
+        .map(|x| match x {
+            None => String::new(),
+            Some(x) if x.is_empty() || x.contains(|c| c == delimiter || "\"\r\n".contains(c)) => {
+                format!("\"{}\"", x.replace('"', "\"\""))
+            }
+            Some(x) => x.to_string(),
+        }).collect::<Vec<String>>()
+        .join(&delimiter.to_string())
+}
+
+/// A row with its column meta, to tell blob colums from text mysql also returns as bytes
+pub(crate) struct MetaRow(pub Vec<ColumnMeta>, pub DynRow);
+
+impl Queryable for MetaRow {
+    fn new(row: Row) -> Self {
+        MetaRow(row.column_meta(), <DynRow as Queryable>::new(row))
+    }
+}
+
+impl MetaRow {
+    /// If the colum at i is declared as blob or binary
+    pub(crate) fn is_binary(&self, i: usize) -> bool {
+        match self.0.get(i).and_then(|x| x.decl_type.as_ref()) {
+            Some(t) => is_binary(t),
+            None => false,
+        }
+    }
+}
+
+pub(crate) fn is_binary(decl_type: &str) -> bool {
+    SqlType::from_decl(decl_type) == Some(SqlType::Bytes)
+}
+
+pub(crate) fn export_csv<C, P, W>(
+    con: &C,
+    sql: &str,
+    params: P,
+    mut writer: W,
+    options: &CsvOptions,
+) -> Desult<u64>
+where
+    C: Connectionable,
+    P: std::clone::Clone,
+    Params: std::convert::From<P>,
+    W: Write,
+{
+    let delimiter = options.delimiter;
+    let mut count = 0;
+
+    let columns = con.for_each_row(sql, params, |row: MetaRow| {
+        if count == 0 {
+            let names = row.1.names().into_iter().map(Some);
+            write_line(&mut writer, &csv_record(names, delimiter))?;
+        }
+
+        let texts: Vec<Option<String>> = row
+            .1
+            .iter()
+            .enumerate()
+            .map(|(i, x)| text(&x.1, row.is_binary(i)))
+            .collect();
+        write_line(
+            &mut writer,
+            &csv_record(texts.iter().map(|x| x.as_ref().map(|x| x.as_str())), delimiter),
+        )?;
+        count += 1;
+        Ok(())
+    })?;
+
+    // Without rows the header comes from the result set, sqlite reports none
+    if count == 0 && !columns.is_empty() {
+        write_line(
+            &mut writer,
+            &csv_record(columns.iter().map(|x| Some(x.name.as_str())), delimiter),
+        )?;
+    }
+
+    writer.flush().map_err(io_err)?;
+    Ok(count)
+}
+
+pub(crate) fn import_csv<C, R>(
+    con: &C,
+    table: &str,
+    reader: R,
+    options: &CsvOptions,
+) -> Desult<ImportReport>
+where
+    C: Connectionable,
+    R: Read,
+{
+    let schema = con.schema()?;
+    let table_schema = match schema.table(table) {
+        Some(x) => x,
+        None => return Err(Error::LibErr(format!("No table {}", table))),
+    };
+
+    let mut reader = CsvReader {
+        reader: BufReader::new(reader),
+        options,
+        line: 0,
+    };
+    let header = match reader.next_record()? {
+        Some(x) => x.1,
+        None => return Ok(ImportReport::default()),
+    };
+
+    let mut columns: Vec<&ColumnSchema> = Vec::new();
+    for n in header {
+        let colum = find_column(table_schema, &n.unwrap_or_default())
+            .map_err(|e| Error::LibErr(format!("Line 1: {}", e)))?;
+        columns.push(colum);
+    }
+    let names: Vec<String> = columns.iter().map(|x| x.name.clone()).collect();
+
+    let mut report = ImportReport::default();
+    let mut chunk: Vec<(usize, Vec<Dypes>)> = Vec::new();
+
+    while let Some((line, record)) = reader.next_record()? {
+        match convert(record, &columns) {
+            Ok(values) => chunk.push((line, values)),
+            Err(e) => skip_or_fail(&mut report, options.skip_errors, line, e)?,
+        }
+
+        if chunk.len() >= options.chunk_size.max(1) {
+            insert_chunk(con, table, &names, &mut chunk, options.skip_errors, &mut report)?;
+        }
+    }
+    insert_chunk(con, table, &names, &mut chunk, options.skip_errors, &mut report)?;
+
+    Ok(report)
+}
+
+/// Reads the records of a csv stream
+struct CsvReader<'a, R> {
+    reader: R,
+    options: &'a CsvOptions,
+    /// Lines read so far
+    line: usize,
+}
+
+impl<'a, R: BufRead> CsvReader<'a, R> {
+    /// Next record and the line it starts on, None at the end. Blank lines are skipped
+    fn next_record(&mut self) -> Desult<Option<(usize, Vec<Option<String>>)>> {
+        let mut buf = String::new();
+        let mut start = self.line + 1;
+
+        loop {
+            let n = self.reader.read_line(&mut buf).map_err(io_err)?;
+            if n == 0 {
+                if buf.is_empty() {
+                    return Ok(None);
+                }
+                if self.open_quote(&buf) {
+                    return Err(Error::LibErr(format!("Line {}: unterminated quote", start)));
+                }
+                break;
+            }
+            self.line += 1;
+
+            if buf.trim().is_empty() {
+                buf.clear();
+                start = self.line + 1;
+                continue;
+            }
+            // A quoted field can hold line breaks
+            if !self.open_quote(&buf) {
+                break;
+            }
+        }
+
+        if buf.ends_with('\n') {
+            buf.pop();
+            if buf.ends_with('\r') {
+                buf.pop();
+            }
+        }
+
+        Ok(Some((start, self.parse(&buf))))
+    }
+
+    fn parse(&self, record: &str) -> Vec<Option<String>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut chars = record.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if c != '"' {
+                    field.push(c);
+                } else if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else if c == '"' && field.is_empty() && !quoted {
+                in_quotes = true;
+                quoted = true;
+            } else if c == self.options.delimiter {
+                fields.push(self.field(field, quoted));
+                field = String::new();
+                quoted = false;
+            } else {
+                field.push(c);
+            }
+        }
+
+        fields.push(self.field(field, quoted));
+        fields
+    }
+
+    fn field(&self, field: String, quoted: bool) -> Option<String> {
+        match self.options.null {
+            Some(ref x) if !quoted && *x == field => None,
+            _ => Some(field),
+        }
+    }
+
+    /// True when text ends inside a quoted field. As in parse only a quote at the start
+    /// of a field opens one
+    fn open_quote(&self, text: &str) -> bool {
+        let mut in_quotes = false;
+        let mut field_start = true;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if c == '"' && chars.peek() == Some(&'"') {
+                    chars.next();
+                } else if c == '"' {
+                    in_quotes = false;
+                }
+            } else if c == '"' && field_start {
+                in_quotes = true;
+                field_start = false;
+            } else {
+                field_start = c == self.options.delimiter;
+            }
+        }
+
+        in_quotes
+    }
+}
+
+/// Values of a record converted to the types of columns
+fn convert(record: Vec<Option<String>>, columns: &[&ColumnSchema]) -> Desult<Vec<Dypes>> {
+    if record.len() != columns.len() {
+        return Err(Error::LibErr(format!(
+            "Expected {} fields, got {}",
+            columns.len(),
+            record.len()
+        )));
+    }
+
+    record
+        .into_iter()
+        .zip(columns.iter())
+        .map(|(x, colum)| match x {
+            Some(x) => coerce(x, colum),
+            None => Ok(Dypes::Null),
+        }).collect()
+}
+
+/// Text of a field as a value for the declared type of colum
+fn coerce(text: String, colum: &ColumnSchema) -> Desult<Dypes> {
+    let err = |x: &str, kind: &str| {
+        Error::ConversionErr(format!("{:?} is not {} for {}", x, kind, colum.name))
+    };
+
+    match SqlType::from_decl(&colum.data_type) {
+        Some(SqlType::Bool) => match text.trim().to_lowercase().as_str() {
+            "1" | "true" | "t" | "yes" => Ok(Dypes::from(true)),
+            "0" | "false" | "f" | "no" => Ok(Dypes::from(false)),
+            _ => Err(err(&text, "a boolean")),
+        },
+        Some(SqlType::Int) | Some(SqlType::Uint) | Some(SqlType::BigInt)
+        | Some(SqlType::BigUint) => {
+            let x = text.trim();
+            match (x.parse::<i64>(), x.parse::<u64>()) {
+                (Ok(x), _) => Ok(Dypes::Int(x)),
+                (_, Ok(x)) => Ok(Dypes::Uint(x)),
+                _ => Err(err(&text, "an integer")),
+            }
+        }
+        Some(SqlType::Float) | Some(SqlType::Double) => text
+            .trim()
+            .parse::<f64>()
+            .map(Dypes::Float)
+            .map_err(|_| err(&text, "a number")),
+        Some(SqlType::Bytes) => base64::decode(text.trim())
+            .map(Dypes::Bytes)
+            .map_err(|_| err(&text, "base64")),
+        // Decimals stay text so no precision is lost
+        _ => Ok(Dypes::String(text)),
+    }
+}
+
+/// Colum of table named name, exact match first then ignoring case
+pub(crate) fn find_column<'a>(table: &'a TableSchema, name: &str) -> Desult<&'a ColumnSchema> {
+    table
+        .column(name)
+        .or_else(|| {
+            table
+                .columns
+                .iter()
+                .find(|x| x.name.eq_ignore_ascii_case(name))
+        }).ok_or_else(|| Error::LibErr(format!("No colum {:?} in {}", name, table.name)))
+}
+
+/// Insert the rows of chunk, each with the line it was read from, and empty it.
+/// With skip_errors a failing chunk is retried row by row and the failing lines reported
+pub(crate) fn insert_chunk<C: Connectionable>(
+    con: &C,
+    table: &str,
+    names: &[String],
+    chunk: &mut Vec<(usize, Vec<Dypes>)>,
+    skip_errors: bool,
+    report: &mut ImportReport,
+) -> Desult<()> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    let rows = chunk.split_off(0);
+
+    match insert_rows(con, table, names, &rows) {
+        Ok(()) => report.inserted += rows.len() as u64,
+        // Row by row to find the lines that fail
+        Err(_) if skip_errors => {
+            for n in rows {
+                match insert_rows(con, table, names, std::slice::from_ref(&n)) {
+                    Ok(()) => report.inserted += 1,
+                    Err(e) => skip_or_fail(report, skip_errors, n.0, e)?,
+                }
+            }
+        }
+        Err(e) => {
+            return Err(Error::LibErr(format!(
+                "Lines {}-{}: {}",
+                rows[0].0,
+                rows[rows.len() - 1].0,
+                e
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+fn insert_rows<C: Connectionable>(
+    con: &C,
+    table: &str,
+    names: &[String],
+    rows: &[(usize, Vec<Dypes>)],
+) -> Desult<()> {
+    let sql = C::dialect().insert_sql(table, names, rows.len())?;
+    let values: Vec<Dypes> = rows.iter().flat_map(|x| x.1.iter().cloned()).collect();
+    con.execute(&sql, Params::new(values))
+}
+
+/// Report the error of line with skip_errors, else fail with it
+pub(crate) fn skip_or_fail(
+    report: &mut ImportReport,
+    skip_errors: bool,
+    line: usize,
+    e: Error,
+) -> Desult<()> {
+    if !skip_errors {
+        return Err(Error::LibErr(format!("Line {}: {}", line, e)));
+    }
+
+    report.errors.push(LineError {
+        line,
+        message: e.to_string(),
+    });
+    Ok(())
+}
+
+/// Text of a value, None for NULL. Bytes of binary colums and bytes that are not utf8
+/// are written as base64
+fn text(val: &Dypes, binary: bool) -> Option<String> {
+    match val {
+        Dypes::Uint(x) => Some(x.to_string()),
+        Dypes::Int(x) => Some(x.to_string()),
+        Dypes::Float(x) => Some(x.to_string()),
+        Dypes::String(x) => Some(x.clone()),
+        Dypes::Bytes(x) => match std::str::from_utf8(x) {
+            Ok(s) if !binary => Some(s.to_string()),
+            _ => Some(base64::encode(x)),
+        },
+        Dypes::Null => None,
+        Dypes::List(_) => None,
+    }
+}
+
+pub(crate) fn write_line<W: Write>(writer: &mut W, line: &str) -> Desult<()> {
+    writer.write_all(line.as_bytes()).map_err(io_err)?;
+    writer.write_all(b"\n").map_err(io_err)
+}
+
+pub(crate) fn io_err(e: std::io::Error) -> Error {
+    Error::LibErr(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader<'a>(text: &'a str, options: &'a CsvOptions) -> CsvReader<'a, &'a [u8]> {
+        CsvReader {
+            reader: text.as_bytes(),
+            options,
+            line: 0,
+        }
+    }
+
+    fn some(x: &str) -> Option<String> {
+        Some(x.to_string())
+    }
+
+    #[test]
+    fn parse_fields() {
+        let options = CsvOptions::default();
+        let r = reader("", &options);
+
+        assert_eq!(
+            r.parse(r#"1,"a,b","say ""hi""",,"""#),
+            vec![some("1"), some("a,b"), some("say \"hi\""), None, some("")]
+        );
+        // A quote inside an unquoted field is kept
+        assert_eq!(r.parse(r#"5,3" pipe,x"#), vec![some("5"), some("3\" pipe"), some("x")]);
+    }
+
+    #[test]
+    fn parse_null_and_delimiter() {
+        let options = CsvOptions {
+            delimiter: ';',
+            null: Some("NULL".to_string()),
+            ..Default::default()
+        };
+        let r = reader("", &options);
+
+        assert_eq!(
+            r.parse(r#"a,b;NULL;"NULL";"#),
+            vec![some("a,b"), None, some("NULL"), some("")]
+        );
+    }
+
+    #[test]
+    fn open_quote_only_at_field_start() {
+        let options = CsvOptions::default();
+        let r = reader("", &options);
+
+        assert!(r.open_quote("1,\"a\n"));
+        assert!(!r.open_quote("1,\"a\"\"b\""));
+        assert!(!r.open_quote("5,3\" pipe,x"));
+        assert!(r.open_quote("5,3\" pipe,\"x"));
+    }
+
+    #[test]
+    fn next_record_joins_quoted_lines() {
+        let options = CsvOptions::default();
+        let mut r = reader("id,text\n\n1,\"a\nb\"\r\n2,3\" pipe\n", &options);
+
+        assert_eq!(r.next_record().unwrap(), Some((1, vec![some("id"), some("text")])));
+        assert_eq!(r.next_record().unwrap(), Some((3, vec![some("1"), some("a\nb")])));
+        assert_eq!(r.next_record().unwrap(), Some((5, vec![some("2"), some("3\" pipe")])));
+        assert_eq!(r.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn csv_record_quotes() {
+        assert_eq!(
+            csv_record(vec![Some("a"), None, Some(""), Some("b,\"c\"")], ','),
+            "a,,\"\",\"b,\"\"c\"\"\""
+        );
+        assert_eq!(
+            csv_record(vec![Some("a,b"), Some("c;d")], ';'),
+            "a,b;\"c;d\""
+        );
+    }
+}
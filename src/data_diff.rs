@@ -0,0 +1,534 @@
+//! Rows that differ between two tables, ex: a table and its replica on another backend.
+//! ```ignore
+//! //Untested
+//! let diff = diff_tables(&mysql_con, &sqlite_con, "user", &[]).unwrap();
+//! println!("{}", diff);
+//!
+//! for n in &diff.changed {
+//!     for c in &n.columns {
+//!         println!("{:?} {}: {:?} -> {:?}", n.key, c.name, c.a, c.b);
+//!     }
+//! }
+//! ```
+
+use super::{Connectionable, Desult, DynRow, Dypes, Error, Params, SqlType, TableSchema};
+use std;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+/// Rows per checksum range
+const CHUNK_SIZE: usize = 1000;
+
+/// Rows of table in b that are not in a, in a but not in b, and in both with other values
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DataDiff {
+    /// Rows only in b
+    pub inserted: Vec<DynRow>,
+    /// Rows only in a
+    pub deleted: Vec<DynRow>,
+    pub changed: Vec<RowChange>,
+    /// Number of rows in a
+    pub rows_a: u64,
+    /// Number of rows in b
+    pub rows_b: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RowChange {
+    /// Values of the key colums
+    pub key: Vec<Dypes>,
+    pub columns: Vec<ColumnChange>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ColumnChange {
+    pub name: String,
+    pub a: Dypes,
+    pub b: Dypes,
+}
+
+impl DataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.deleted.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl std::fmt::Display for DataDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rows in a, {} in b: {} inserted, {} deleted, {} changed",
+            self.rows_a,
+            self.rows_b,
+            self.inserted.len(),
+            self.deleted.len(),
+            self.changed.len()
+        )
+    }
+}
+
+/// A range of keys, after lower up to and including upper. None is unbounded
+struct Range {
+    lower: Option<Vec<Dypes>>,
+    upper: Option<Vec<Dypes>>,
+    checksum: u64,
+    count: u64,
+}
+
+/// Compare the rows of table on a and b by key_columns, the primary key of table in a
+/// when empty. The colums both tables have are compared.
+/// Rows of a are checksummed in key ranges, only the rows of ranges whose checksum
+/// differs in b are compared row by row. Keys should sort the same on both backends,
+/// like integers. Text mysql returns as bytes is compared as text, and numbers of
+/// colums with a numeric type on either side by value, ex: DECIMAL '12.50' and REAL 12.5
+pub fn diff_tables<A, B>(a: &A, b: &B, table: &str, key_columns: &[&str]) -> Desult<DataDiff>
+where
+    A: Connectionable,
+    B: Connectionable,
+{
+    let schema_a = a.schema()?;
+    let schema_b = b.schema()?;
+    let table_a = find_table(schema_a.table(table), table, "a")?;
+    let table_b = find_table(schema_b.table(table), table, "b")?;
+
+    let keys: Vec<String> = if key_columns.is_empty() {
+        table_a.primary_key.clone()
+    } else {
+        key_columns.iter().map(|x| x.to_string()).collect()
+    };
+    if keys.is_empty() {
+        return Err(Error::LibErr(format!("No key colums for {}", table)));
+    }
+
+    let columns: Vec<String> = table_a
+        .columns
+        .iter()
+        .filter(|x| table_b.column(&x.name).is_some())
+        .map(|x| x.name.clone())
+        .collect();
+    let numeric: Vec<bool> = columns
+        .iter()
+        .map(|x| {
+            [table_a.column(x), table_b.column(x)]
+                .iter()
+                .any(|c| c.map(|c| is_numeric(&c.data_type)).unwrap_or(false))
+        }).collect();
+    let key_index: Vec<usize> = keys
+        .iter()
+        .map(|k| {
+            columns.iter().position(|x| x == k).ok_or_else(|| {
+                Error::LibErr(format!("No key colum {} in both tables {}", k, table))
+            })
+        }).collect::<Desult<_>>()?;
+
+    let query = Query {
+        table,
+        columns: &columns,
+        numeric: &numeric,
+        keys: &keys,
+        key_index: &key_index,
+    };
+
+    let mut diff = DataDiff::default();
+    for n in query.ranges(a)? {
+        let (checksum, count) = query.checksum(b, &n)?;
+        diff.rows_a += n.count;
+        diff.rows_b += count;
+
+        if checksum != n.checksum || count != n.count {
+            query.compare(a, b, &n, &mut diff)?;
+        }
+    }
+
+    Ok(diff)
+}
+
+fn find_table<'a>(
+    table: Option<&'a TableSchema>,
+    name: &str,
+    side: &str,
+) -> Desult<&'a TableSchema> {
+    table.ok_or_else(|| Error::LibErr(format!("No table {} in {}", name, side)))
+}
+
+struct Query<'a> {
+    table: &'a str,
+    columns: &'a [String],
+    /// If the colum at the same index holds numbers
+    numeric: &'a [bool],
+    keys: &'a [String],
+    key_index: &'a [usize],
+}
+
+impl<'a> Query<'a> {
+    /// Key ranges of CHUNK_SIZE rows of con, the last one is unbounded
+    fn ranges<C: Connectionable>(&self, con: &C) -> Desult<Vec<Range>> {
+        let (sql, params) = self.select_sql::<C>(&None, &None)?;
+        let mut rt = Vec::new();
+        let mut current = Range {
+            lower: None,
+            upper: None,
+            checksum: 0,
+            count: 0,
+        };
+
+        con.for_each_row(&sql, params, |row: DynRow| {
+            let values = normalize(row);
+            current.checksum = current.checksum.wrapping_add(self.row_hash(&values));
+            current.count += 1;
+
+            if current.count as usize >= CHUNK_SIZE {
+                let upper = Some(self.key(&values));
+                let next = Range {
+                    lower: upper.clone(),
+                    upper: None,
+                    checksum: 0,
+                    count: 0,
+                };
+                current.upper = upper;
+                rt.push(std::mem::replace(&mut current, next));
+            }
+            Ok(())
+        })?;
+
+        rt.push(current);
+        Ok(rt)
+    }
+
+    /// Checksum and number of the rows of con in range
+    fn checksum<C: Connectionable>(&self, con: &C, range: &Range) -> Desult<(u64, u64)> {
+        let (sql, params) = self.select_sql::<C>(&range.lower, &range.upper)?;
+        let mut checksum: u64 = 0;
+        let mut count = 0;
+
+        con.for_each_row(&sql, params, |row: DynRow| {
+            checksum = checksum.wrapping_add(self.row_hash(&normalize(row)));
+            count += 1;
+            Ok(())
+        })?;
+
+        Ok((checksum, count))
+    }
+
+    /// Add the differences of the rows in range to diff
+    fn compare<A, B>(&self, a: &A, b: &B, range: &Range, diff: &mut DataDiff) -> Desult<()>
+    where
+        A: Connectionable,
+        B: Connectionable,
+    {
+        let rows_a = self.rows(a, range)?;
+        let rows_b = self.rows(b, range)?;
+
+        let mut by_key: HashMap<String, usize> = HashMap::new();
+        for (i, n) in rows_b.iter().enumerate() {
+            by_key.insert(self.key_str(n), i);
+        }
+        let mut matched = vec![false; rows_b.len()];
+
+        for n in rows_a {
+            let i = match by_key.get(&self.key_str(&n)) {
+                Some(i) => *i,
+                None => {
+                    diff.deleted.push(self.dyn_row(n));
+                    continue;
+                }
+            };
+            matched[i] = true;
+
+            let key = self.key(&n);
+            let columns: Vec<ColumnChange> = self
+                .columns
+                .iter()
+                .zip(self.numeric)
+                .zip(n.into_iter().zip(rows_b[i].iter()))
+                .filter(|x| value_hash(&(x.1).0, *(x.0).1) != value_hash((x.1).1, *(x.0).1))
+                .map(|((name, _), (a, b))| ColumnChange {
+                    name: name.clone(),
+                    a,
+                    b: b.clone(),
+                }).collect();
+            if !columns.is_empty() {
+                diff.changed.push(RowChange { key, columns });
+            }
+        }
+
+        for (i, n) in rows_b.into_iter().enumerate() {
+            if !matched[i] {
+                diff.inserted.push(self.dyn_row(n));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rows<C: Connectionable>(&self, con: &C, range: &Range) -> Desult<Vec<Vec<Dypes>>> {
+        let (sql, params) = self.select_sql::<C>(&range.lower, &range.upper)?;
+        let mut rt = Vec::new();
+
+        con.for_each_row(&sql, params, |row: DynRow| {
+            rt.push(normalize(row));
+            Ok(())
+        })?;
+
+        Ok(rt)
+    }
+
+    /// SELECT of the rows after lower up to upper, ordered by key
+    fn select_sql<C: Connectionable>(
+        &self,
+        lower: &Option<Vec<Dypes>>,
+        upper: &Option<Vec<Dypes>>,
+    ) -> Desult<(String, Params)> {
+        let d = C::dialect();
+        // Text keys are ordered by their bytes, so both backends split the ranges
+        // at the same rows whatever the collation of the colums
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .zip(self.key_index)
+            .map(|(x, i)| match d.ident(x) {
+                Ok(ref x) if !self.numeric[*i] => Ok(d.binary(x)),
+                x => x,
+            }).collect::<Desult<_>>()?;
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|x| d.ident(x))
+            .collect::<Desult<_>>()?;
+
+        let mut conds = Vec::new();
+        let mut params = Vec::new();
+        if let Some(ref x) = *lower {
+            conds.push(key_cond::<C>(&keys, x, ">", ">", &mut params));
+        }
+        if let Some(ref x) = *upper {
+            conds.push(key_cond::<C>(&keys, x, "<", "<=", &mut params));
+        }
+
+        let mut sql = format!("SELECT {} FROM {}", columns.join(", "), d.ident(self.table)?);
+        if !conds.is_empty() {
+            sql += &format!(" WHERE {}", conds.join(" AND "));
+        }
+        sql += &format!(" ORDER BY {}", keys.join(", "));
+
+        Ok((sql, Params::new(params)))
+    }
+
+    fn key(&self, values: &[Dypes]) -> Vec<Dypes> {
+        self.key_index.iter().map(|i| values[*i].clone()).collect()
+    }
+
+    /// Key of values to match rows of a and b by
+    fn key_str(&self, values: &[Dypes]) -> String {
+        let key: Vec<Dypes> = self
+            .key_index
+            .iter()
+            .map(|i| match number(&values[*i], self.numeric[*i]) {
+                Some(x) => Dypes::String(x),
+                None => values[*i].clone(),
+            }).collect();
+        format!("{:?}", key)
+    }
+
+    fn row_hash(&self, values: &[Dypes]) -> u64 {
+        let mut h = DefaultHasher::new();
+        for (n, numeric) in values.iter().zip(self.numeric) {
+            h.write_u64(value_hash(n, *numeric));
+        }
+        h.finish()
+    }
+
+    fn dyn_row(&self, values: Vec<Dypes>) -> DynRow {
+        DynRow::new(self.columns.iter().cloned().zip(values).collect())
+    }
+}
+
+/// Row comparison of keys with key, ex: for > on (a, b): (a > ? OR (a = ? AND b > ?)).
+/// op is used for all but the last key, last for the last one
+fn key_cond<C: Connectionable>(
+    keys: &[String],
+    key: &[Dypes],
+    op: &str,
+    last: &str,
+    params: &mut Vec<Dypes>,
+) -> String {
+    let q = C::dialect().placeholder();
+    let mut ors = Vec::new();
+
+    for i in 0..keys.len() {
+        let mut ands = Vec::new();
+        for j in 0..i {
+            ands.push(format!("{} = {}", keys[j], q));
+            params.push(key[j].clone());
+        }
+        let op = if i + 1 == keys.len() { last } else { op };
+        ands.push(format!("{} {} {}", keys[i], op, q));
+        params.push(key[i].clone());
+        ors.push(ands.join(" AND "));
+    }
+
+    if ors.len() == 1 {
+        ors.remove(0)
+    } else {
+        format!("(({}))", ors.join(") OR ("))
+    }
+}
+
+/// Values of a row in a form both backends return the same
+fn normalize(row: DynRow) -> Vec<Dypes> {
+    row.into_values()
+        .into_iter()
+        .map(|x| match x.1 {
+            Dypes::Bytes(b) => match String::from_utf8(b) {
+                Ok(s) => Dypes::String(s),
+                Err(e) => Dypes::Bytes(e.into_bytes()),
+            },
+            Dypes::Int(n) if n >= 0 => Dypes::Uint(n as u64),
+            x => x,
+        }).collect()
+}
+
+/// If decl is a numeric colum type
+fn is_numeric(decl: &str) -> bool {
+    match SqlType::from_decl(decl) {
+        Some(SqlType::Text) | Some(SqlType::Bytes) | Some(SqlType::Date) | None => false,
+        Some(_) => true,
+    }
+}
+
+/// Number as decimal text without leading and trailing zeros, ex: "12.50" and 12.5 to "12.5".
+/// None when val is not a number or the colum is not numeric
+fn number(val: &Dypes, numeric: bool) -> Option<String> {
+    if !numeric {
+        return None;
+    }
+    let text = match val {
+        Dypes::Uint(x) => return Some(x.to_string()),
+        Dypes::Int(x) => return Some(x.to_string()),
+        Dypes::Float(x) if x.is_finite() => x.to_string(),
+        Dypes::String(x) => x.trim().to_string(),
+        _ => return None,
+    };
+
+    let f = text.parse::<f64>().ok()?;
+    if !f.is_finite() {
+        return None;
+    }
+    if text.contains(&['e', 'E'][..]) {
+        return number(&Dypes::Float(f), true);
+    }
+
+    let (negative, digits) = match text.chars().next() {
+        Some('-') => (true, &text[1..]),
+        Some('+') => (false, &text[1..]),
+        _ => (false, &text[..]),
+    };
+    let mut parts = digits.splitn(2, '.');
+    let int = parts.next().unwrap_or("").trim_start_matches('0');
+    let frac = parts.next().unwrap_or("").trim_end_matches('0');
+
+    let mut rt = if int.is_empty() { "0".to_string() } else { int.to_string() };
+    if !frac.is_empty() {
+        rt = format!("{}.{}", rt, frac);
+    }
+    if negative && rt != "0" {
+        rt.insert(0, '-');
+    }
+    Some(rt)
+}
+
+/// Hash of val, numbers of numeric colums hash by value
+fn value_hash(val: &Dypes, numeric: bool) -> u64 {
+    let mut h = DefaultHasher::new();
+    if let Some(x) = number(val, numeric) {
+        h.write_u8(7);
+        h.write(x.as_bytes());
+        return h.finish();
+    }
+
+    match val {
+        Dypes::Uint(x) => {
+            h.write_u8(0);
+            h.write_u64(*x);
+        }
+        Dypes::Int(x) => {
+            h.write_u8(1);
+            h.write_i64(*x);
+        }
+        Dypes::Float(x) => {
+            h.write_u8(2);
+            h.write_u64(x.to_bits());
+        }
+        Dypes::String(x) => {
+            h.write_u8(3);
+            h.write(x.as_bytes());
+        }
+        Dypes::Bytes(x) => {
+            h.write_u8(4);
+            h.write(x);
+        }
+        Dypes::Null => h.write_u8(5),
+        Dypes::List(x) => {
+            h.write_u8(6);
+            for n in x {
+                h.write_u64(value_hash(n, numeric));
+            }
+        }
+    }
+    h.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlite;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn key_cond_single_key() {
+        let mut params = Vec::new();
+        let key = [Dypes::Uint(5)];
+        let sql = key_cond::<sqlite::Connection>(&keys(&["id"]), &key, ">", ">=", &mut params);
+
+        assert_eq!(sql, "id >= ?");
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn key_cond_compound_key() {
+        let mut params = Vec::new();
+        let key = [Dypes::Uint(1), Dypes::from("b")];
+        let sql =
+            key_cond::<sqlite::Connection>(&keys(&["a", "b"]), &key, "<", "<=", &mut params);
+
+        assert_eq!(sql, "((a < ?) OR (a = ? AND b <= ?))");
+        match (&params[0], &params[1], &params[2]) {
+            (Dypes::Uint(1), Dypes::Uint(1), Dypes::String(x)) => assert_eq!(x, "b"),
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        let n = |x: Dypes| number(&x, true);
+
+        assert_eq!(n(Dypes::from("12.50")), Some("12.5".to_string()));
+        assert_eq!(n(Dypes::Float(12.5)), Some("12.5".to_string()));
+        assert_eq!(n(Dypes::from("-0.00")), Some("0".to_string()));
+        assert_eq!(n(Dypes::from("007")), Some("7".to_string()));
+        assert_eq!(n(Dypes::from("1e3")), Some("1000".to_string()));
+        assert_eq!(n(Dypes::Uint(3)), n(Dypes::Float(3.0)));
+        assert_eq!(n(Dypes::from("abc")), None);
+        assert_eq!(number(&Dypes::from("007"), false), None);
+
+        assert_eq!(
+            value_hash(&Dypes::from("12.50"), true),
+            value_hash(&Dypes::Float(12.5), true)
+        );
+        assert!(value_hash(&Dypes::from("7"), false) != value_hash(&Dypes::from("007"), false));
+    }
+}
@@ -0,0 +1,283 @@
+//! serde::Deserializer over a Row, used by `Connectionable::select_de`
+
+use super::{ConvMode, Desult, Dypes, Error, FromDypes, Queryable, Row};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use std;
+use std::fmt;
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::ConversionErr(msg.to_string())
+    }
+}
+
+/// Queryable wrapper used to run select with a Deserialize type
+pub(crate) struct De<T>(pub Desult<T>);
+
+impl<T> Queryable for De<T>
+where
+    T: DeserializeOwned,
+{
+    fn new(row: Row) -> Self {
+        De(T::deserialize(RowDeserializer::new(&row)))
+    }
+}
+
+impl<T> fmt::Debug for De<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "De")
+    }
+}
+
+/// Deserializes a whole row as a struct or map.
+/// Struct fields are matched to columns named `prefix + field`
+pub struct RowDeserializer<'a, 'b: 'a> {
+    row: &'a Row<'b>,
+    columns: std::rc::Rc<Vec<String>>,
+    prefix: String,
+}
+
+impl<'a, 'b> RowDeserializer<'a, 'b> {
+    pub fn new(row: &'a Row<'b>) -> Self {
+        RowDeserializer {
+            row,
+            columns: std::rc::Rc::new(row.columns()),
+            prefix: String::new(),
+        }
+    }
+
+    fn nested(&self, prefix: String) -> Self {
+        RowDeserializer {
+            row: self.row,
+            columns: self.columns.clone(),
+            prefix,
+        }
+    }
+
+    fn has_column(&self, name: &str) -> bool {
+        self.columns.iter().any(|x| x == name)
+    }
+
+    fn prefixed(&self, prefix: &str) -> Vec<&String> {
+        self.columns
+            .iter()
+            .filter(|x| x.starts_with(prefix))
+            .collect()
+    }
+
+    fn value(&self, name: &str) -> Desult<Dypes> {
+        self.row.try_get::<Dypes>(name)
+    }
+}
+
+impl<'de, 'a, 'b> de::Deserializer<'de> for RowDeserializer<'a, 'b> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        let all_null = self
+            .prefixed(&self.prefix)
+            .iter()
+            .all(|x| match self.value(x) {
+                Ok(Dypes::Null) => true,
+                _ => false,
+            });
+
+        if all_null {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Desult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        let keys: Vec<(String, String)> = self
+            .prefixed(&self.prefix)
+            .into_iter()
+            .map(|x| (x[self.prefix.len()..].to_string(), x.clone()))
+            .collect();
+
+        visitor.visit_map(RowMap {
+            de: self,
+            keys: keys.into_iter(),
+            next: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Desult<V::Value> {
+        // Only fields that have a column or prefixed columns are handed to serde,
+        // missing ones fall back to serde's Option/default handling
+        let keys: Vec<(String, String)> = fields
+            .iter()
+            .filter_map(|f| {
+                let name = format!("{}{}", self.prefix, f);
+                if self.has_column(&name) || !self.prefixed(&format!("{}_", name)).is_empty() {
+                    Some((f.to_string(), name))
+                } else {
+                    None
+                }
+            }).collect();
+
+        visitor.visit_map(RowMap {
+            de: self,
+            keys: keys.into_iter(),
+            next: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct enum identifier
+        ignored_any
+    }
+}
+
+struct RowMap<'a, 'b: 'a> {
+    de: RowDeserializer<'a, 'b>,
+    keys: std::vec::IntoIter<(String, String)>,
+    next: Option<String>,
+}
+
+impl<'de, 'a, 'b> de::MapAccess<'de> for RowMap<'a, 'b> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Desult<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.keys.next() {
+            Some((key, column)) => {
+                self.next = Some(column);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Desult<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let column = match self.next.take() {
+            Some(x) => x,
+            None => return Err(Error::LibErr("Value requested before key".to_string())),
+        };
+
+        if self.de.has_column(&column) {
+            let val = self.de.value(&column)?;
+            seed.deserialize(DypesDeserializer::new(val, column, self.de.row.conv_mode()))
+        } else {
+            seed.deserialize(self.de.nested(format!("{}_", column)))
+        }
+    }
+}
+
+/// Deserializes a single column value
+pub struct DypesDeserializer {
+    val: Dypes,
+    column: String,
+    mode: ConvMode,
+}
+
+impl DypesDeserializer {
+    pub fn new(val: Dypes, column: String, mode: ConvMode) -> Self {
+        DypesDeserializer { val, column, mode }
+    }
+
+    fn convert<T: FromDypes>(self) -> Desult<T> {
+        let column = self.column;
+        T::from_dypes(self.val, self.mode).map_err(|e| match e {
+            Error::ConversionErr(e) => Error::ConversionErr(format!("{}: {}", column, e)),
+            e => e,
+        })
+    }
+}
+
+macro_rules! deserialize_checked {
+    ($fun:ident, $visit:ident, $t:ty) => {
+        fn $fun<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+            visitor.$visit(self.convert::<$t>()?)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for DypesDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        match self.val {
+            Dypes::Uint(x) => visitor.visit_u64(x),
+            Dypes::Int(x) => visitor.visit_i64(x),
+            Dypes::Float(x) => visitor.visit_f64(x),
+            Dypes::String(x) => visitor.visit_string(x),
+            Dypes::Bytes(x) => visitor.visit_byte_buf(x),
+            Dypes::Null => visitor.visit_unit(),
+            Dypes::List(_) => Err(Error::ConversionErr("A list is not a column value".to_string())),
+        }
+    }
+
+    deserialize_checked!(deserialize_bool, visit_bool, bool);
+    deserialize_checked!(deserialize_i32, visit_i32, i32);
+    deserialize_checked!(deserialize_i64, visit_i64, i64);
+    deserialize_checked!(deserialize_u32, visit_u32, u32);
+    deserialize_checked!(deserialize_u64, visit_u64, u64);
+    deserialize_checked!(deserialize_f32, visit_f32, f32);
+    deserialize_checked!(deserialize_f64, visit_f64, f64);
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        match self.val {
+            Dypes::Bytes(_) | Dypes::String(_) => visitor.visit_string(self.convert::<String>()?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Desult<V::Value> {
+        match self.val {
+            Dypes::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Desult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Desult<V::Value> {
+        let val: String = self.convert()?;
+        visitor.visit_enum(val.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 u8 u16 char bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
@@ -0,0 +1,796 @@
+//! SQL differences between mysql and sqlite.
+//! Connectionable::dialect returns the dialect of a connection, the
+//! dialects can also be used directly to write portable sql.
+//! ```ignore
+//! //Untested
+//! let d = DbCon::dialect();
+//! let sql = format!(
+//!     "SELECT {} AS name FROM {} WHERE created < {}",
+//!     d.concat_ws(" ", &["first_name", "last_name"]),
+//!     d.quote_ident("user"),
+//!     d.current_timestamp()
+//! );
+//! ```
+
+use super::{
+    ColumnSchema, Desult, Dypes, Error, ForeignKeySchema, IndexSchema, SchemaChange, SqlType,
+    TableSchema,
+};
+use std;
+
+pub trait Dialect {
+    /// Name of the backend, "mysql" or "sqlite"
+    fn name(&self) -> &'static str;
+
+    /// Quote a table or column name.
+    /// Dotted names like table.colum are quoted per part and * is left as is
+    fn quote_ident(&self, ident: &str) -> String;
+
+    /// Placeholder for a bound param
+    fn placeholder(&self) -> &'static str {
+        "?"
+    }
+
+    /// If a backslash escapes the next character in string literals
+    fn backslash_escapes(&self) -> bool {
+        false
+    }
+
+    /// If :name, @name and $name are placeholders the backend binds by itself,
+    /// in the order they are first used
+    fn named_placeholders(&self) -> bool {
+        true
+    }
+
+    /// Value as an sql literal, bytes as a hex literal.
+    /// Floats that are not finite become NULL
+    fn literal(&self, val: &Dypes) -> String {
+        match val {
+            Dypes::Uint(x) => x.to_string(),
+            Dypes::Int(x) => x.to_string(),
+            Dypes::Float(x) if x.is_finite() => format!("{:?}", x),
+            Dypes::Float(_) | Dypes::Null => "NULL".to_string(),
+            Dypes::String(x) if self.backslash_escapes() => quote_str(&x.replace('\\', "\\\\")),
+            Dypes::String(x) => quote_str(x),
+            Dypes::Bytes(x) => {
+                let hex: Vec<String> = x.iter().map(|b| format!("{:02X}", b)).collect();
+                format!("X'{}'", hex.concat())
+            }
+            Dypes::List(x) => {
+                let vals: Vec<String> = x.iter().map(|x| self.literal(x)).collect();
+                vals.join(", ")
+            }
+        }
+    }
+
+    /// n placeholders separated by commas
+    fn placeholders(&self, n: usize) -> String {
+        let q_arr: Vec<&str> = std::iter::repeat(self.placeholder()).take(n).collect();
+        q_arr.join(",")
+    }
+
+    /// expr compared and sorted by its bytes whatever the collation of its colum
+    fn binary(&self, expr: &str) -> String {
+        format!("{} COLLATE BINARY", expr)
+    }
+
+    /// Subquery with no rows, bound for an empty list so IN matches nothing
+    /// and NOT IN matches everything
+    fn empty_list(&self) -> &'static str {
+        "SELECT NULL WHERE 1 = 0"
+    }
+
+    /// Clause appended to an INSERT to update the colums of existing rows.
+    /// keys are the unique colums the conflict is detected on,
+    /// colums and keys are expected to be quoted
+    fn upsert_clause(&self, colums: &[String], keys: &[String]) -> String;
+
+    /// LIMIT and OFFSET clause
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String;
+
+    /// Concatenate string expressions
+    fn concat(&self, exprs: &[&str]) -> String;
+
+    /// Concatenate string expressions with sep between them
+    fn concat_ws(&self, sep: &str, exprs: &[&str]) -> String;
+
+    /// Current date and time expression
+    fn current_timestamp(&self) -> &'static str {
+        "CURRENT_TIMESTAMP"
+    }
+
+    /// Boolean literal
+    fn bool_literal(&self, val: bool) -> &'static str;
+
+    /// Format a date expression, format uses strftime specifiers
+    /// (%Y, %m, %d, %H, %M, %S)
+    fn date_format(&self, expr: &str, format: &str) -> String;
+
+    /// Date expression plus days, days can be negative
+    fn add_days(&self, expr: &str, days: i64) -> String;
+
+    /// If INSERT, UPDATE and DELETE support RETURNING
+    fn supports_returning(&self) -> bool;
+
+    /// Column type for t, ex: "BIGINT UNSIGNED" for mysql or "INTEGER" for sqlite
+    fn sql_type(&self, t: SqlType) -> &'static str;
+
+    /// Definition of colum inside CREATE TABLE
+    fn column_sql(&self, table: &TableSchema, colum: &ColumnSchema) -> Desult<String>;
+
+    /// If column_sql already declares the primary key of table
+    fn inline_primary_key(&self, _table: &TableSchema) -> bool {
+        false
+    }
+
+    /// CREATE INDEX for an index of table
+    fn create_index_sql(&self, table: &TableSchema, index: &IndexSchema) -> Desult<String>;
+
+    /// Statements applying a schema change, see Schema::diff
+    fn alter_sql(&self, change: &SchemaChange) -> Desult<Vec<String>>;
+
+    /// Validate and quote a table or column name, see validate_ident
+    fn ident(&self, ident: &str) -> Desult<String> {
+        validate_ident(ident)?;
+        Ok(self.quote_ident(ident))
+    }
+
+    /// INSERT INTO table (colums) VALUES (?,..),.. for rows rows
+    fn insert_sql(&self, table: &str, colums: &[String], rows: usize) -> Desult<String> {
+        let single = format!("({})", self.placeholders(colums.len()));
+        let q_arr: Vec<&str> = std::iter::repeat(single.as_str()).take(rows).collect();
+        Ok(format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.ident(table)?,
+            idents(self, colums)?.join(","),
+            q_arr.join(",")
+        ))
+    }
+
+    /// insert_sql followed by upsert_clause
+    fn upsert_sql(
+        &self,
+        table: &str,
+        colums: &[String],
+        keys: &[String],
+        rows: usize,
+    ) -> Desult<String> {
+        Ok(format!(
+            "{} {}",
+            self.insert_sql(table, colums, rows)?,
+            self.upsert_clause(&idents(self, colums)?, &idents(self, keys)?)
+        ))
+    }
+
+    /// UPDATE table SET colum = ?,.. WHERE cond?,..
+    /// Each of where_keys holds a colum and an optional operator, ex: "id = " or "id >"
+    fn update_sql(&self, table: &str, colums: &[String], where_keys: &[&str]) -> Desult<String> {
+        let vars: Vec<String> = idents(self, colums)?
+            .into_iter()
+            .map(|x| format!("{} = {}", x, self.placeholder()))
+            .collect();
+
+        let mut where_str = Vec::new();
+        for n in where_keys {
+            let (colum, op) = split_where_key(n)?;
+            where_str.push(format!("{} {} {}", self.ident(colum)?, op, self.placeholder()));
+        }
+
+        Ok(format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.ident(table)?,
+            vars.join(","),
+            where_str.join(" and ")
+        ))
+    }
+
+    /// DELETE FROM table WHERE id_colum IN (?), in_out is IN or NOT IN.
+    /// The ids are bound as a single list param, see In
+    fn delete_in_sql(&self, table: &str, id_colum: &str, in_out: &str) -> Desult<String> {
+        let in_out = in_out.trim().to_uppercase();
+        if in_out != "IN" && in_out != "NOT IN" {
+            return Err(Error::LibErr(format!("Expected IN or NOT IN, got {}", in_out)));
+        }
+
+        Ok(format!(
+            "delete from {} where {} {} ({})",
+            self.ident(table)?,
+            self.ident(id_colum)?,
+            in_out,
+            self.placeholder()
+        ))
+    }
+
+    /// CREATE TABLE for table followed by a CREATE INDEX per index
+    fn create_table_sql(&self, table: &TableSchema) -> Desult<Vec<String>> {
+        let mut defs = Vec::new();
+        for n in &table.columns {
+            defs.push(self.column_sql(table, n)?);
+        }
+
+        if !table.primary_key.is_empty() && !self.inline_primary_key(table) {
+            defs.push(format!(
+                "PRIMARY KEY ({})",
+                idents(self, &table.primary_key)?.join(", ")
+            ));
+        }
+
+        for n in &table.foreign_keys {
+            defs.push(foreign_key_sql(self, n)?);
+        }
+
+        let mut rt = vec![format!(
+            "CREATE TABLE {} (\n    {}\n)",
+            self.ident(&table.qualified_name())?,
+            defs.join(",\n    ")
+        )];
+        for n in &table.indexes {
+            rt.push(self.create_index_sql(table, n)?);
+        }
+
+        Ok(rt)
+    }
+}
+
+fn idents<D: Dialect + ?Sized>(d: &D, idents: &[String]) -> Desult<Vec<String>> {
+    idents.iter().map(|x| d.ident(x)).collect()
+}
+
+/// colum name data_type [NOT NULL] [DEFAULT x] followed by extra
+fn column_def<D: Dialect + ?Sized>(
+    d: &D,
+    colum: &ColumnSchema,
+    data_type: &str,
+    extra: &str,
+) -> Desult<String> {
+    let mut rt = format!("{} {}", d.ident(&colum.name)?, data_type);
+    if !colum.nullable {
+        rt += " NOT NULL";
+    }
+    if let Some(ref x) = colum.default {
+        rt += &format!(" DEFAULT {}", x);
+    }
+    rt += extra;
+    Ok(rt)
+}
+
+fn foreign_key_sql<D: Dialect + ?Sized>(d: &D, fk: &ForeignKeySchema) -> Desult<String> {
+    let mut rt = match fk.name {
+        Some(ref x) => format!("CONSTRAINT {} ", d.ident(x)?),
+        None => "".to_string(),
+    };
+    rt += &format!(
+        "FOREIGN KEY ({}) REFERENCES {}",
+        idents(d, &fk.columns)?.join(", "),
+        d.ident(&fk.ref_table)?
+    );
+    if !fk.ref_columns.is_empty() {
+        rt += &format!(" ({})", idents(d, &fk.ref_columns)?.join(", "));
+    }
+    if let Some(ref x) = fk.on_delete {
+        rt += &format!(" ON DELETE {}", referential_action(x)?);
+    }
+    if let Some(ref x) = fk.on_update {
+        rt += &format!(" ON UPDATE {}", referential_action(x)?);
+    }
+    Ok(rt)
+}
+
+/// Statements for the changes both dialects apply the same way
+fn alter_sql_common<D: Dialect + ?Sized>(d: &D, change: &SchemaChange) -> Desult<Vec<String>> {
+    Ok(match change {
+        SchemaChange::AddTable(x) => d.create_table_sql(x)?,
+        SchemaChange::DropTable(x) => vec![format!("DROP TABLE {}", d.ident(x)?)],
+        SchemaChange::AddColumn(t, x) => vec![format!(
+            "ALTER TABLE {} ADD COLUMN {}",
+            d.ident(t)?,
+            d.column_sql(&TableSchema::named(t), x)?
+        )],
+        SchemaChange::DropColumn(t, x) => vec![format!(
+            "ALTER TABLE {} DROP COLUMN {}",
+            d.ident(t)?,
+            d.ident(x)?
+        )],
+        SchemaChange::AddIndex(t, x) => vec![d.create_index_sql(&TableSchema::named(t), x)?],
+        x => {
+            return Err(Error::LibErr(format!(
+                "{} cannot apply {} in place, the table has to be recreated",
+                d.name(),
+                x
+            )))
+        }
+    })
+}
+
+fn referential_action(action: &str) -> Desult<String> {
+    let action = action.trim().to_uppercase();
+    match action.as_str() {
+        "CASCADE" | "SET NULL" | "SET DEFAULT" | "RESTRICT" | "NO ACTION" => Ok(action),
+        _ => Err(Error::LibErr(format!("Invalid referential action {}", action))),
+    }
+}
+
+fn unique_str(index: &IndexSchema) -> &'static str {
+    if index.unique {
+        "UNIQUE "
+    } else {
+        ""
+    }
+}
+
+/// The single auto increment primary key colum of table, for sqlite an INTEGER PRIMARY KEY
+fn rowid_key(table: &TableSchema) -> Option<&ColumnSchema> {
+    if table.primary_key.len() != 1 {
+        return None;
+    }
+    table
+        .column(&table.primary_key[0])
+        .filter(|x| x.auto_increment)
+}
+
+/// Comparison operators accepted in generated where conditions
+pub(crate) const OPERATORS: [&str; 9] = ["=", "!=", "<>", "<", "<=", ">", ">=", "LIKE", "NOT LIKE"];
+
+/// Check that ident is a valid table or column name.
+/// Names may be qualified as schema.table or table.colum, each part can only hold
+/// letters, digits, _ and $ and may not start with a digit
+pub fn validate_ident(ident: &str) -> Desult<()> {
+    let parts: Vec<&str> = ident.split('.').collect();
+
+    if parts.len() > 3 {
+        return Err(Error::InvalidIdent(format!("{} has too many parts", ident)));
+    }
+
+    for n in parts {
+        let mut chars = n.chars();
+        let valid = match chars.next() {
+            Some(c) => {
+                (c.is_ascii_alphabetic() || c == '_')
+                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            }
+            None => false,
+        };
+
+        if !valid || n.len() > 64 {
+            return Err(Error::InvalidIdent(format!("{:?}", ident)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Split an update where key like "id >= " into colum and operator,
+/// the operator defaults to =
+fn split_where_key(key: &str) -> Desult<(&str, String)> {
+    let key = key.trim();
+    let end = key
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '.'))
+        .unwrap_or(key.len());
+    let (colum, op) = key.split_at(end);
+
+    let op = match op.trim() {
+        "" => "=".to_string(),
+        x => x.to_uppercase(),
+    };
+
+    if !OPERATORS.contains(&op.as_str()) {
+        return Err(Error::LibErr(format!("Invalid operator {}", op)));
+    }
+
+    Ok((colum, op))
+}
+
+/// Quote every part of a dotted identifier with q, doubling q inside names
+fn quote_with(ident: &str, q: char) -> String {
+    ident
+        .split('.')
+        .map(|x| {
+            if x == "*" {
+                x.to_string()
+            } else {
+                let escaped = x.replace(q, &format!("{}{}", q, q));
+                format!("{}{}{}", q, escaped, q)
+            }
+        }).collect::<Vec<String>>()
+        .join(".")
+}
+
+fn quote_str(val: &str) -> String {
+    format!("'{}'", val.replace('\'', "''"))
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MysqlDialect;
+
+impl Dialect for MysqlDialect {
+    fn name(&self) -> &'static str {
+        "mysql"
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        quote_with(ident, '`')
+    }
+
+    fn backslash_escapes(&self) -> bool {
+        true
+    }
+
+    /// @name is a user variable, :name is not valid sql
+    fn named_placeholders(&self) -> bool {
+        false
+    }
+
+    fn binary(&self, expr: &str) -> String {
+        format!("CAST({} AS BINARY)", expr)
+    }
+
+    fn empty_list(&self) -> &'static str {
+        "SELECT NULL FROM DUAL WHERE 1 = 0"
+    }
+
+    fn upsert_clause(&self, colums: &[String], _keys: &[String]) -> String {
+        let vars: Vec<String> = colums
+            .iter()
+            .map(|n| format!("{} = VALUES({}) ", n, n))
+            .collect();
+        format!("ON DUPLICATE KEY UPDATE {}", vars.join(","))
+    }
+
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        // mysql has no OFFSET without LIMIT, the max value is used as no limit
+        let limit = limit.unwrap_or(std::u64::MAX);
+        match offset {
+            Some(x) => format!("LIMIT {} OFFSET {}", limit, x),
+            None => format!("LIMIT {}", limit),
+        }
+    }
+
+    fn concat(&self, exprs: &[&str]) -> String {
+        format!("CONCAT({})", exprs.join(", "))
+    }
+
+    fn concat_ws(&self, sep: &str, exprs: &[&str]) -> String {
+        format!("concat_ws({}, {})", quote_str(sep), exprs.join(","))
+    }
+
+    fn bool_literal(&self, val: bool) -> &'static str {
+        if val {
+            "TRUE"
+        } else {
+            "FALSE"
+        }
+    }
+
+    fn date_format(&self, expr: &str, format: &str) -> String {
+        let format = format.replace("%M", "%i").replace("%S", "%s");
+        format!("DATE_FORMAT({}, {})", expr, quote_str(&format))
+    }
+
+    fn add_days(&self, expr: &str, days: i64) -> String {
+        format!("DATE_ADD({}, INTERVAL {} DAY)", expr, days)
+    }
+
+    fn supports_returning(&self) -> bool {
+        false
+    }
+
+    fn sql_type(&self, t: SqlType) -> &'static str {
+        match t {
+            SqlType::Bool => "TINYINT(1)",
+            SqlType::Int => "INT",
+            SqlType::Uint => "INT UNSIGNED",
+            SqlType::BigInt => "BIGINT",
+            SqlType::BigUint => "BIGINT UNSIGNED",
+            SqlType::Float => "FLOAT",
+            SqlType::Double => "DOUBLE",
+            SqlType::Decimal => "DECIMAL(65,30)",
+            SqlType::Date => "DATETIME",
+            // TEXT cannot be a key without a prefix length
+            SqlType::Text => "VARCHAR(255)",
+            SqlType::Bytes => "BLOB",
+        }
+    }
+
+    fn column_sql(&self, _table: &TableSchema, colum: &ColumnSchema) -> Desult<String> {
+        let extra = if colum.auto_increment {
+            " AUTO_INCREMENT"
+        } else {
+            ""
+        };
+        column_def(self, colum, &colum.data_type, extra)
+    }
+
+    fn create_index_sql(&self, table: &TableSchema, index: &IndexSchema) -> Desult<String> {
+        Ok(format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            unique_str(index),
+            self.ident(&index.name)?,
+            self.ident(&table.qualified_name())?,
+            idents(self, &index.columns)?.join(", ")
+        ))
+    }
+
+    fn alter_sql(&self, change: &SchemaChange) -> Desult<Vec<String>> {
+        Ok(match change {
+            SchemaChange::AlterColumn(t, _, x) => vec![format!(
+                "ALTER TABLE {} MODIFY COLUMN {}",
+                self.ident(t)?,
+                self.column_sql(&TableSchema::named(t), x)?
+            )],
+            SchemaChange::AlterPrimaryKey(t, from, to) => {
+                let mut rt = Vec::new();
+                if !from.is_empty() {
+                    rt.push(format!("ALTER TABLE {} DROP PRIMARY KEY", self.ident(t)?));
+                }
+                if !to.is_empty() {
+                    rt.push(format!(
+                        "ALTER TABLE {} ADD PRIMARY KEY ({})",
+                        self.ident(t)?,
+                        idents(self, to)?.join(", ")
+                    ));
+                }
+                rt
+            }
+            SchemaChange::DropIndex(t, x) => vec![format!(
+                "DROP INDEX {} ON {}",
+                self.ident(x)?,
+                self.ident(t)?
+            )],
+            SchemaChange::AddForeignKey(t, x) => vec![format!(
+                "ALTER TABLE {} ADD {}",
+                self.ident(t)?,
+                foreign_key_sql(self, x)?
+            )],
+            SchemaChange::DropForeignKey(t, x) => match x.name {
+                Some(ref name) => vec![format!(
+                    "ALTER TABLE {} DROP FOREIGN KEY {}",
+                    self.ident(t)?,
+                    self.ident(name)?
+                )],
+                None => {
+                    return Err(Error::LibErr(format!(
+                        "Foreign key of {} has no name to drop it by",
+                        t
+                    )))
+                }
+            },
+            x => alter_sql_common(self, x)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SqliteDialect;
+
+impl Dialect for SqliteDialect {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn quote_ident(&self, ident: &str) -> String {
+        quote_with(ident, '"')
+    }
+
+    /// Needs sqlite 3.24, and 3.35 when keys is empty.
+    /// See sqlite::Connection::version
+    fn upsert_clause(&self, colums: &[String], keys: &[String]) -> String {
+        let vars: Vec<String> = colums
+            .iter()
+            .map(|n| format!("{} = excluded.{}", n, n))
+            .collect();
+        let target = if keys.is_empty() {
+            "".to_string()
+        } else {
+            format!("({}) ", keys.join(","))
+        };
+        format!("ON CONFLICT {}DO UPDATE SET {}", target, vars.join(","))
+    }
+
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        // A negative limit means no limit in sqlite
+        let limit = match limit {
+            Some(x) => x.to_string(),
+            None => "-1".to_string(),
+        };
+        match offset {
+            Some(x) => format!("LIMIT {} OFFSET {}", limit, x),
+            None => format!("LIMIT {}", limit),
+        }
+    }
+
+    fn concat(&self, exprs: &[&str]) -> String {
+        format!("({})", exprs.join(" || "))
+    }
+
+    fn concat_ws(&self, sep: &str, exprs: &[&str]) -> String {
+        let sep = format!(" || {} || ", quote_str(sep));
+        format!("({})", exprs.join(&sep))
+    }
+
+    fn bool_literal(&self, val: bool) -> &'static str {
+        if val {
+            "1"
+        } else {
+            "0"
+        }
+    }
+
+    fn date_format(&self, expr: &str, format: &str) -> String {
+        format!("strftime({}, {})", quote_str(format), expr)
+    }
+
+    fn add_days(&self, expr: &str, days: i64) -> String {
+        format!("datetime({}, '{:+} days')", expr, days)
+    }
+
+    /// RETURNING needs sqlite 3.35, older versions fail to prepare the statement.
+    /// See sqlite::Connection::version
+    fn supports_returning(&self) -> bool {
+        true
+    }
+
+    fn sql_type(&self, t: SqlType) -> &'static str {
+        match t {
+            SqlType::Bool
+            | SqlType::Int
+            | SqlType::Uint
+            | SqlType::BigInt
+            | SqlType::BigUint => "INTEGER",
+            SqlType::Float | SqlType::Double => "REAL",
+            SqlType::Decimal => "NUMERIC",
+            SqlType::Date => "DATETIME",
+            SqlType::Text => "TEXT",
+            SqlType::Bytes => "BLOB",
+        }
+    }
+
+    /// An auto increment primary key is declared as INTEGER PRIMARY KEY,
+    /// the alias of the rowid
+    fn column_sql(&self, table: &TableSchema, colum: &ColumnSchema) -> Desult<String> {
+        match rowid_key(table) {
+            Some(x) if x.name == colum.name => column_def(self, colum, "INTEGER", " PRIMARY KEY"),
+            _ => column_def(self, colum, &colum.data_type, ""),
+        }
+    }
+
+    fn inline_primary_key(&self, table: &TableSchema) -> bool {
+        rowid_key(table).is_some()
+    }
+
+    /// The index is created in the database of table
+    fn create_index_sql(&self, table: &TableSchema, index: &IndexSchema) -> Desult<String> {
+        let name = match table.schema {
+            Some(ref x) => format!("{}.{}", x, index.name),
+            None => index.name.clone(),
+        };
+        Ok(format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            unique_str(index),
+            self.ident(&name)?,
+            self.ident(&table.name)?,
+            idents(self, &index.columns)?.join(", ")
+        ))
+    }
+
+    /// Colums, primary keys and foreign keys of existing tables cannot be altered.
+    /// DROP COLUMN needs sqlite 3.35
+    fn alter_sql(&self, change: &SchemaChange) -> Desult<Vec<String>> {
+        match change {
+            SchemaChange::DropIndex(t, x) => {
+                let name = match TableSchema::named(t).schema {
+                    Some(schema) => format!("{}.{}", schema, x),
+                    None => x.clone(),
+                };
+                Ok(vec![format!("DROP INDEX {}", self.ident(&name)?)])
+            }
+            x => alter_sql_common(self, x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_table() -> TableSchema {
+        let column = |name: &str, data_type: &str, nullable: bool| ColumnSchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable,
+            ..Default::default()
+        };
+        TableSchema {
+            name: "user".to_string(),
+            columns: vec![
+                ColumnSchema {
+                    auto_increment: true,
+                    ..column("id", "INTEGER", false)
+                },
+                ColumnSchema {
+                    default: Some("'a'".to_string()),
+                    ..column("name", "TEXT", false)
+                },
+                column("age", "INTEGER", true),
+            ],
+            primary_key: vec!["id".to_string()],
+            indexes: vec![IndexSchema {
+                name: "user_name".to_string(),
+                columns: vec!["name".to_string()],
+                unique: true,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_idents() {
+        assert!(validate_ident("user").is_ok());
+        assert!(validate_ident("_tmp$1").is_ok());
+        assert!(validate_ident("main.user.id").is_ok());
+        assert!(validate_ident("a.b.c.d").is_err());
+        assert!(validate_ident("1user").is_err());
+        assert!(validate_ident("user name").is_err());
+        assert!(validate_ident("user;DROP").is_err());
+        assert!(validate_ident("user.").is_err());
+        assert!(validate_ident("").is_err());
+        assert!(validate_ident(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(MysqlDialect.quote_ident("user.id"), "`user`.`id`");
+        assert_eq!(MysqlDialect.quote_ident("a`b"), "`a``b`");
+        assert_eq!(MysqlDialect.quote_ident("user.*"), "`user`.*");
+        assert_eq!(SqliteDialect.quote_ident("user.id"), "\"user\".\"id\"");
+        assert_eq!(SqliteDialect.quote_ident("a\"b"), "\"a\"\"b\"");
+
+        let s = Dypes::String("it's \\".to_string());
+        assert_eq!(MysqlDialect.literal(&s), "'it''s \\\\'");
+        assert_eq!(SqliteDialect.literal(&s), "'it''s \\'");
+        assert_eq!(SqliteDialect.literal(&Dypes::Bytes(vec![1, 171])), "X'01AB'");
+        assert_eq!(SqliteDialect.literal(&Dypes::Float(std::f64::NAN)), "NULL");
+
+        assert!(MysqlDialect.ident("user name").is_err());
+    }
+
+    #[test]
+    fn limit_offset() {
+        assert_eq!(MysqlDialect.limit_offset(Some(10), None), "LIMIT 10");
+        assert_eq!(MysqlDialect.limit_offset(Some(10), Some(20)), "LIMIT 10 OFFSET 20");
+        assert_eq!(
+            MysqlDialect.limit_offset(None, Some(20)),
+            format!("LIMIT {} OFFSET 20", std::u64::MAX)
+        );
+        assert_eq!(SqliteDialect.limit_offset(Some(10), None), "LIMIT 10");
+        assert_eq!(SqliteDialect.limit_offset(None, Some(20)), "LIMIT -1 OFFSET 20");
+    }
+
+    #[test]
+    fn create_table() {
+        assert_eq!(
+            MysqlDialect.create_table_sql(&user_table()).unwrap(),
+            vec![
+                "CREATE TABLE `user` (\n    \
+                 `id` INTEGER NOT NULL AUTO_INCREMENT,\n    \
+                 `name` TEXT NOT NULL DEFAULT 'a',\n    \
+                 `age` INTEGER,\n    \
+                 PRIMARY KEY (`id`)\n)",
+                "CREATE UNIQUE INDEX `user_name` ON `user` (`name`)",
+            ]
+        );
+        assert_eq!(
+            SqliteDialect.create_table_sql(&user_table()).unwrap(),
+            vec![
+                "CREATE TABLE \"user\" (\n    \
+                 \"id\" INTEGER NOT NULL PRIMARY KEY,\n    \
+                 \"name\" TEXT NOT NULL DEFAULT 'a',\n    \
+                 \"age\" INTEGER\n)",
+                "CREATE UNIQUE INDEX \"user_name\" ON \"user\" (\"name\")",
+            ]
+        );
+
+        let mut table = user_table();
+        table.name = "user name".to_string();
+        assert!(SqliteDialect.create_table_sql(&table).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! SQL dumps of a database, see Connectionable::dump and Connectionable::restore.
+//! A dump can be written for the other backend, to copy a mysql database to sqlite:
+//! ```ignore
+//! //Untested
+//! let mut file = std::fs::File::create("app.sql").unwrap();
+//! let options = DumpOptions {
+//!     dialect: Some(&SqliteDialect),
+//!     ..Default::default()
+//! };
+//! mysql_con.dump(&mut file, &options).unwrap();
+//!
+//! let file = std::fs::File::open("app.sql").unwrap();
+//! sqlite_con.restore(file).unwrap();
+//! ```
+
+use super::csv::io_err;
+use super::{
+    split_statements, ColumnSchema, Connectionable, Desult, Dialect, DynRow, Dypes, Error, Params,
+    SqlType, TableSchema,
+};
+use std;
+use std::io::{Read, Write};
+
+/// Options of Connectionable::dump.
+/// A mysql dump with schema is not restored atomically, mysql commits implicitly on
+/// DROP TABLE and CREATE TABLE
+#[derive(Clone)]
+pub struct DumpOptions {
+    /// Tables to dump, all tables when empty
+    pub tables: Vec<String>,
+    /// Dialect the dump is written for, the dialect of the connection when None
+    pub dialect: Option<&'static dyn Dialect>,
+    /// Write CREATE TABLE and CREATE INDEX statements, defaults to true
+    pub schema: bool,
+    /// Write INSERT statements, defaults to true
+    pub data: bool,
+    /// Drop the tables before they are created, defaults to false
+    pub drop_tables: bool,
+    /// Rows per INSERT, defaults to 500
+    pub batch_size: usize,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        DumpOptions {
+            tables: Vec::new(),
+            dialect: None,
+            schema: true,
+            data: true,
+            drop_tables: false,
+            batch_size: 500,
+        }
+    }
+}
+
+pub(crate) fn dump<C, W>(con: &C, mut writer: W, options: &DumpOptions) -> Desult<()>
+where
+    C: Connectionable,
+    W: Write,
+{
+    let source = C::dialect();
+    let target = options.dialect.unwrap_or(source);
+
+    let schema = con.schema()?;
+    let mut tables: Vec<&TableSchema> = Vec::new();
+    if options.tables.is_empty() {
+        tables.extend(schema.tables.iter());
+    } else {
+        for n in &options.tables {
+            match schema.table(n) {
+                Some(x) => tables.push(x),
+                None => return Err(Error::LibErr(format!("No table {}", n))),
+            }
+        }
+    }
+    let tables = dependency_order(tables);
+
+    let mut out = Out {
+        writer: &mut writer,
+    };
+    out.line(&format!(
+        "-- dengine dump of {} tables from {} for {}",
+        tables.len(),
+        source.name(),
+        target.name()
+    ))?;
+
+    // Rows of a table can reference rows further down the same table
+    match target.name() {
+        "mysql" => out.statement("SET FOREIGN_KEY_CHECKS = 0")?,
+        "sqlite" => out.statement("PRAGMA defer_foreign_keys = ON")?,
+        _ => (),
+    }
+
+    if options.schema && options.drop_tables {
+        for n in tables.iter().rev() {
+            out.statement(&format!("DROP TABLE IF EXISTS {}", target.ident(&n.name)?))?;
+        }
+    }
+
+    for n in &tables {
+        let table = portable_table(n, source, target);
+
+        if options.schema {
+            out.line("")?;
+            for sql in target.create_table_sql(&table)? {
+                out.statement(&sql)?;
+            }
+        }
+        if options.data {
+            dump_rows(con, &mut out, n, &table, target, options.batch_size.max(1))?;
+        }
+    }
+
+    if target.name() == "mysql" {
+        out.statement("SET FOREIGN_KEY_CHECKS = 1")?;
+    }
+
+    writer.flush().map_err(io_err)
+}
+
+pub(crate) fn restore<C, R>(con: &C, mut reader: R) -> Desult<usize>
+where
+    C: Connectionable,
+    R: Read,
+{
+    let mut sql = String::new();
+    reader.read_to_string(&mut sql).map_err(io_err)?;
+
+    let statements: Vec<(String, Params)> = split_statements(&sql, C::dialect())
+        .into_iter()
+        .map(|x| (x.to_string(), Params::from(())))
+        .collect();
+    let count = statements.len();
+
+    con.execute_transaction(statements)?;
+    Ok(count)
+}
+
+struct Out<'a, W: 'a> {
+    writer: &'a mut W,
+}
+
+impl<'a, W: Write> Out<'a, W> {
+    fn line(&mut self, line: &str) -> Desult<()> {
+        writeln!(self.writer, "{}", line).map_err(io_err)
+    }
+
+    fn statement(&mut self, sql: &str) -> Desult<()> {
+        writeln!(self.writer, "{};", sql).map_err(io_err)
+    }
+}
+
+/// INSERT statements of batch_size rows for the rows of source,
+/// table is source as written for target
+fn dump_rows<C, W>(
+    con: &C,
+    out: &mut Out<W>,
+    source: &TableSchema,
+    table: &TableSchema,
+    target: &dyn Dialect,
+    batch_size: usize,
+) -> Desult<()>
+where
+    C: Connectionable,
+    W: Write,
+{
+    if table.columns.is_empty() {
+        return Ok(());
+    }
+
+    let columns: Vec<String> = source
+        .columns
+        .iter()
+        .map(|x| C::quote_ident(&x.name))
+        .collect();
+    let sql = format!(
+        "SELECT {} FROM {}",
+        columns.join(", "),
+        C::quote_ident(&source.qualified_name())
+    );
+    let names: Vec<String> = table
+        .columns
+        .iter()
+        .map(|x| target.quote_ident(&x.name))
+        .collect();
+    let head = format!(
+        "INSERT INTO {} ({}) VALUES\n",
+        target.quote_ident(&table.name),
+        names.join(", ")
+    );
+
+    let mut rows: Vec<String> = Vec::new();
+    con.for_each_row(&sql, (), |row: DynRow| {
+        let values: Vec<String> = row
+            .iter()
+            .zip(table.columns.iter())
+            .map(|(x, colum)| target.literal(&text_bytes(&x.1, colum)))
+            .collect();
+        rows.push(format!("({})", values.join(", ")));
+
+        if rows.len() >= batch_size {
+            out.statement(&format!("{}{}", head, rows.join(",\n")))?;
+            rows.clear();
+        }
+        Ok(())
+    })?;
+
+    if !rows.is_empty() {
+        out.statement(&format!("{}{}", head, rows.join(",\n")))?;
+    }
+    Ok(())
+}
+
+/// Mysql returns text as bytes, those are written as strings unless colum is binary
+pub(crate) fn text_bytes(val: &Dypes, colum: &ColumnSchema) -> Dypes {
+    match val {
+        Dypes::Bytes(x) if SqlType::from_decl(&colum.data_type) != Some(SqlType::Bytes) => {
+            match std::str::from_utf8(x) {
+                Ok(s) => Dypes::String(s.to_string()),
+                Err(_) => val.clone(),
+            }
+        }
+        x => x.clone(),
+    }
+}
+
+/// Tables ordered so referenced tables come first, tables in a cycle keep their order
+fn dependency_order(mut tables: Vec<&TableSchema>) -> Vec<&TableSchema> {
+    let mut rt: Vec<&TableSchema> = Vec::new();
+
+    while !tables.is_empty() {
+        let ready = tables.iter().position(|t| {
+            t.foreign_keys.iter().all(|fk| {
+                fk.ref_table == t.name || !tables.iter().any(|x| x.name == fk.ref_table)
+            })
+        });
+        rt.push(tables.remove(ready.unwrap_or(0)));
+    }
+
+    rt
+}
+
+/// table with types and defaults the target dialect accepts.
+/// Tables of attached sqlite databases are written to the main database
+pub(crate) fn portable_table(
+    table: &TableSchema,
+    source: &dyn Dialect,
+    target: &dyn Dialect,
+) -> TableSchema {
+    let mut rt = table.clone();
+    rt.schema = None;
+    if source.name() == target.name() {
+        return rt;
+    }
+
+    // mysql cannot index TEXT without a prefix length
+    let keys: Vec<&String> = table
+        .primary_key
+        .iter()
+        .chain(table.indexes.iter().flat_map(|x| x.columns.iter()))
+        .chain(table.foreign_keys.iter().flat_map(|x| x.columns.iter()))
+        .collect();
+
+    for n in &mut rt.columns {
+        n.data_type = portable_type(&n.data_type, keys.contains(&&n.name), target);
+        n.default = n.default.as_ref().map(|x| portable_default(x));
+    }
+    rt
+}
+
+/// Declared type for target, keyed is true for colums of keys and indexes
+fn portable_type(decl: &str, keyed: bool, target: &dyn Dialect) -> String {
+    let t = decl.trim().to_lowercase();
+    // Up to a trailing CHARACTER SET or COLLATE
+    let sized = t.find(')').map(|i| t[..i + 1].to_uppercase());
+
+    match SqlType::from_decl(&t) {
+        Some(SqlType::Text) if t.starts_with("varchar") || t.starts_with("char") => {
+            sized.unwrap_or_else(|| target.sql_type(SqlType::Text).to_string())
+        }
+        Some(SqlType::Decimal) => {
+            sized.unwrap_or_else(|| target.sql_type(SqlType::Decimal).to_string())
+        }
+        // DATE, TIME, DATETIME and TIMESTAMP, without a fractional precision
+        Some(SqlType::Date) => t
+            .split(|c: char| c == '(' || c.is_whitespace())
+            .next()
+            .unwrap_or("")
+            .to_uppercase(),
+        Some(SqlType::Text) | None if keyed => target.sql_type(SqlType::Text).to_string(),
+        // Text types, enum, set, json and sqlite colums without a type
+        Some(SqlType::Text) | None => "TEXT".to_string(),
+        Some(x) => target.sql_type(x).to_string(),
+    }
+}
+
+/// Default for target, mariadb reports current_timestamp() that sqlite does not take
+fn portable_default(default: &str) -> String {
+    let d = default.trim();
+    let upper = d.to_uppercase();
+
+    if upper.starts_with("CURRENT_") {
+        upper.trim_end_matches("()").to_string()
+    } else {
+        d.to_string()
+    }
+}
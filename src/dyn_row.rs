@@ -0,0 +1,173 @@
+use super::{chrono, Desult, Dypes, Error, Queryable, Row, Rowable};
+use serde;
+use std;
+
+/// Metadata of a result set column.
+/// Fields are None when the backend does not report them
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnMeta {
+    pub name: String,
+    /// Declared type, ex: "VARCHAR" for mysql or the declared type of the column in sqlite
+    pub decl_type: Option<String>,
+    /// Only reported by mysql
+    pub nullable: Option<bool>,
+    /// Table the column originates from, None for expressions
+    pub table: Option<String>,
+}
+
+impl ColumnMeta {
+    pub fn new(name: String) -> Self {
+        ColumnMeta {
+            name,
+            decl_type: None,
+            nullable: None,
+            table: None,
+        }
+    }
+}
+
+/// A row whose shape is only known at runtime.
+/// Holds the column names and values in select order.
+/// ```ignore
+/// //Untested
+/// let res = con.select::<DynRow, _>("SELECT * FROM user", (), false).unwrap();
+///
+/// for col in &res.columns {
+///     println!("{} {:?}", col.name, col.decl_type);
+/// }
+///
+/// for row in res.data {
+///     println!("{:?} {:?}", row[0], row["name"]);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DynRow {
+    values: Vec<(String, Dypes)>,
+}
+
+impl DynRow {
+    pub fn new(values: Vec<(String, Dypes)>) -> Self {
+        DynRow { values }
+    }
+
+    /// Value at column index
+    pub fn get(&self, index: usize) -> Option<&Dypes> {
+        self.values.get(index).map(|x| &x.1)
+    }
+
+    /// Value of the first column named name.
+    /// Use get with an index when the name is not unique
+    pub fn get_by_name(&self, name: &str) -> Option<&Dypes> {
+        self.values.iter().find(|x| x.0 == name).map(|x| &x.1)
+    }
+
+    /// Column names in select order
+    pub fn names(&self) -> Vec<&str> {
+        self.values.iter().map(|x| x.0.as_str()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (String, Dypes)> {
+        self.values.iter()
+    }
+
+    pub fn into_values(self) -> Vec<(String, Dypes)> {
+        self.values
+    }
+}
+
+impl Queryable for DynRow {
+    fn new(row: Row) -> Self {
+        let values = row
+            .columns()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let val = row.get_at::<Dypes>(i).unwrap_or(Dypes::Null);
+                (name, val)
+            }).collect();
+
+        DynRow { values }
+    }
+}
+
+/// Lets a DynRow be converted into any other Queryable
+impl Rowable for DynRow {
+    fn get_val(&self, key: &str) -> Option<Dypes> {
+        self.get_by_name(key).cloned()
+    }
+
+    fn get_val_at(&self, index: usize) -> Option<Dypes> {
+        self.get(index).cloned()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.values.iter().map(|x| x.0.clone()).collect()
+    }
+
+    fn column_meta(&self) -> Vec<ColumnMeta> {
+        self.values
+            .iter()
+            .map(|x| ColumnMeta::new(x.0.clone()))
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn get_date_string(&self, key: &str, format: &str) -> Desult<String> {
+        let val = match self.get_by_name(key) {
+            Some(Dypes::String(x)) => x,
+            _ => return Err(Error::date_conv_err(key)),
+        };
+
+        let date = val.split(' ').next().unwrap_or("");
+        let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_e| Error::date_conv_err(key))?;
+
+        Ok(format!("{}", date.format(format)))
+    }
+}
+
+impl std::ops::Index<usize> for DynRow {
+    type Output = Dypes;
+
+    fn index(&self, index: usize) -> &Dypes {
+        &self.values[index].1
+    }
+}
+
+impl<'a> std::ops::Index<&'a str> for DynRow {
+    type Output = Dypes;
+
+    fn index(&self, name: &str) -> &Dypes {
+        match self.get_by_name(name) {
+            Some(x) => x,
+            None => panic!("No column named {}", name),
+        }
+    }
+}
+
+/// Serialized as a map of column name to value, in select order
+impl serde::Serialize for DynRow {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.values.len()))?;
+        for (k, v) in &self.values {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,180 @@
+//! JSON Lines import and export, see Connectionable::export_jsonl and
+//! Connectionable::import_jsonl. Each line is a JSON object with a key per colum,
+//! bytes of blob and binary colums are base64 strings.
+//! ```ignore
+//! //Untested
+//! let file = std::fs::File::create("user.jsonl").unwrap();
+//! con.export_jsonl("SELECT * FROM user", (), file).unwrap();
+//!
+//! let file = std::fs::File::open("user.jsonl").unwrap();
+//! let report = con.import_jsonl("user", file, &JsonlOptions::default()).unwrap();
+//! ```
+
+use super::csv::{find_column, insert_chunk, io_err, is_binary, skip_or_fail, write_line, MetaRow};
+use super::{
+    base64, serde_json, ColumnSchema, Connectionable, Desult, Dypes, Error, ImportReport, Params,
+    TableSchema,
+};
+use std;
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Options of Connectionable::import_jsonl
+#[derive(Clone, Debug)]
+pub struct JsonlOptions {
+    /// Rows per INSERT, defaults to 500
+    pub chunk_size: usize,
+    /// Report lines that fail to convert or insert and go on, instead of stopping at the first
+    pub skip_errors: bool,
+}
+
+impl Default for JsonlOptions {
+    fn default() -> Self {
+        JsonlOptions {
+            chunk_size: 500,
+            skip_errors: false,
+        }
+    }
+}
+
+pub(crate) fn export_jsonl<C, P, W>(con: &C, sql: &str, params: P, mut writer: W) -> Desult<u64>
+where
+    C: Connectionable,
+    P: std::clone::Clone,
+    Params: std::convert::From<P>,
+    W: Write,
+{
+    let mut count = 0;
+
+    con.for_each_row(sql, params, |row: MetaRow| {
+        // Written by hand so keys keep the select order
+        let mut fields = Vec::new();
+        for (i, x) in row.1.iter().enumerate() {
+            fields.push(format!(
+                "{}:{}",
+                serde_json::Value::from(x.0.as_str()),
+                json_value(&x.1, row.is_binary(i))
+            ));
+        }
+
+        let line = format!("{{{}}}", fields.join(","));
+        write_line(&mut writer, &line)?;
+        count += 1;
+        Ok(())
+    })?;
+
+    writer.flush().map_err(io_err)?;
+    Ok(count)
+}
+
+pub(crate) fn import_jsonl<C, R>(
+    con: &C,
+    table: &str,
+    reader: R,
+    options: &JsonlOptions,
+) -> Desult<ImportReport>
+where
+    C: Connectionable,
+    R: Read,
+{
+    let schema = con.schema()?;
+    let table_schema = match schema.table(table) {
+        Some(x) => x,
+        None => return Err(Error::LibErr(format!("No table {}", table))),
+    };
+
+    let mut report = ImportReport::default();
+    // Rows are inserted together while they have the same keys
+    let mut names: Vec<String> = Vec::new();
+    let mut chunk: Vec<(usize, Vec<Dypes>)> = Vec::new();
+
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(io_err)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (row_names, values) = match convert(&line, table_schema) {
+            Ok(x) => x,
+            Err(e) => {
+                skip_or_fail(&mut report, options.skip_errors, i + 1, e)?;
+                continue;
+            }
+        };
+
+        if row_names != names {
+            insert_chunk(con, table, &names, &mut chunk, options.skip_errors, &mut report)?;
+            names = row_names;
+        }
+        chunk.push((i + 1, values));
+
+        if chunk.len() >= options.chunk_size.max(1) {
+            insert_chunk(con, table, &names, &mut chunk, options.skip_errors, &mut report)?;
+        }
+    }
+    insert_chunk(con, table, &names, &mut chunk, options.skip_errors, &mut report)?;
+
+    Ok(report)
+}
+
+/// Colum names and values of a line
+fn convert(line: &str, table: &TableSchema) -> Desult<(Vec<String>, Vec<Dypes>)> {
+    let object: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(line).map_err(|e| Error::LibErr(e.to_string()))?;
+
+    let mut names = Vec::new();
+    let mut values = Vec::new();
+    for (key, val) in object {
+        let colum = find_column(table, &key)?;
+        values.push(from_json(val, colum)?);
+        names.push(colum.name.clone());
+    }
+
+    if names.is_empty() {
+        return Err(Error::LibErr("Empty object".to_string()));
+    }
+    Ok((names, values))
+}
+
+/// JSON value of a colum as a Dypes. Arrays and objects are stored as their JSON text
+fn from_json(val: serde_json::Value, colum: &ColumnSchema) -> Desult<Dypes> {
+    use serde_json::Value;
+
+    match val {
+        Value::Null => Ok(Dypes::Null),
+        Value::Bool(x) => Ok(Dypes::from(x)),
+        Value::Number(x) => match (x.as_i64(), x.as_u64(), x.as_f64()) {
+            (Some(x), _, _) => Ok(Dypes::Int(x)),
+            (_, Some(x), _) => Ok(Dypes::Uint(x)),
+            (_, _, Some(x)) => Ok(Dypes::Float(x)),
+            _ => Err(Error::ConversionErr(format!("{} is not a number", x))),
+        },
+        Value::String(x) => {
+            if is_binary(&colum.data_type) {
+                base64::decode(&x).map(Dypes::Bytes).map_err(|e| {
+                    Error::ConversionErr(format!("{} is not base64 for {}", e, colum.name))
+                })
+            } else {
+                Ok(Dypes::String(x))
+            }
+        }
+        x => Ok(Dypes::String(x.to_string())),
+    }
+}
+
+/// JSON value of a Dypes. Bytes are base64 for binary colums and when not utf8
+fn json_value(val: &Dypes, binary: bool) -> serde_json::Value {
+    use serde_json::Value;
+
+    match val {
+        Dypes::Uint(x) => Value::from(*x),
+        Dypes::Int(x) => Value::from(*x),
+        Dypes::Float(x) => Value::from(*x),
+        Dypes::String(x) => Value::from(x.as_str()),
+        Dypes::Bytes(x) => match std::str::from_utf8(x) {
+            Ok(s) if !binary => Value::from(s),
+            _ => Value::from(base64::encode(x)),
+        },
+        Dypes::Null => Value::Null,
+        Dypes::List(x) => Value::Array(x.iter().map(|x| json_value(x, binary)).collect()),
+    }
+}
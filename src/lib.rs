@@ -2,18 +2,50 @@
 //! databases.
 
 extern crate deslite;
+extern crate libsqlite3_sys;
 extern crate mysql;
 
+#[macro_use]
 extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+extern crate base64;
+extern crate serde_json;
 
 extern crate chrono;
 extern crate chrono_tz;
 
+pub mod codegen;
+pub mod de;
+pub mod migrations;
 pub mod my_sql;
 pub mod sqlite;
+mod bind;
+mod copy;
+mod data_diff;
+mod csv;
+mod dialect;
+mod dump;
+mod dyn_row;
+mod jsonl;
+mod query_builder;
+mod schema;
+mod schema_diff;
 mod traits;
 mod types;
 
+pub use bind::split_statements;
+pub use copy::*;
+pub use data_diff::*;
+pub use csv::*;
+pub use dialect::*;
+pub use dump::*;
+pub use dyn_row::*;
+pub use jsonl::*;
+pub use query_builder::*;
+pub use schema::*;
+pub use schema_diff::*;
 pub use traits::*;
 pub use types::*;
 
@@ -21,7 +53,7 @@ pub use types::*;
 /// Err defaults to Error
 pub type Desult<T> = Result<T, Error>;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Affected {
     pub affected_rows: u64,
     pub last_insert_id: u64,
@@ -55,15 +87,30 @@ impl DbEngine {
 }
 
 /// Struct returned when the select method is used.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SelectHolder<T> {
     pub data: Vec<T>,
     pub count: usize,
+    /// Result set columns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<ColumnMeta>,
 }
 
 impl<T> SelectHolder<T> {
     pub fn new(data: Vec<T>, count: usize) -> Self {
-        Self { data, count }
+        Self {
+            data,
+            count,
+            columns: Vec::new(),
+        }
+    }
+
+    pub fn with_columns(data: Vec<T>, count: usize, columns: Vec<ColumnMeta>) -> Self {
+        Self {
+            data,
+            count,
+            columns,
+        }
     }
 }
 
@@ -76,10 +123,31 @@ pub enum Error {
     LibErr(String),
     Unknown(String),
     ConnectionErr(String),
+    InvalidIdent(String),
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::SQLErr(x) => write!(f, "SQL error: {}", x),
+            Error::IndexOutOfBound(x) => write!(f, "Index out of bound: {}", x),
+            Error::ConversionErr(x) => write!(f, "Conversion error: {}", x),
+            Error::LibErr(x) => write!(f, "Lib error: {}", x),
+            Error::Unknown(x) => write!(f, "Unknown error: {}", x),
+            Error::ConnectionErr(x) => write!(f, "Connection error: {}", x),
+            Error::InvalidIdent(x) => write!(f, "Invalid identifier: {}", x),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl Error {
     pub fn date_conv_err(key: &str) -> Self {
         Error::ConversionErr(format!("Failed to convert {} to date string", key))
     }
+
+    pub fn dypes_conv_err(val: &Dypes, to: &str) -> Self {
+        Error::ConversionErr(format!("Failed to convert {:?} to {} without loss", val, to))
+    }
 }
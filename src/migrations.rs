@@ -0,0 +1,355 @@
+//! Versioned schema migrations.
+//! Applied versions are recorded with a checksum of their sql in the
+//! `_dengine_migrations` table. Migrations run in version order, each in its own
+//! transaction, and nothing runs when the sql of an applied migration was changed.
+//!
+//! A directory holds one file per version and direction, optionally per dialect:
+//! ```text
+//! 0001_create_user.up.sql
+//! 0001_create_user.down.sql
+//! 0002_add_email.up.sql
+//! 0002_add_email.up.sqlite.sql
+//! ```
+//! ```ignore
+//! //Untested
+//! let migrator = Migrator::from_dir("migrations").unwrap();
+//! let applied = migrator.migrate(&con).unwrap();
+//!
+//! // Or embedded in the binary
+//! let migrator = Migrator::new(vec![
+//!     Migration::new(1, "create_user", include_str!("../migrations/0001_create_user.up.sql"))
+//!         .down("DROP TABLE user"),
+//! ]).unwrap();
+//!
+//! migrator.rollback_to(&con, 0).unwrap();
+//! ```
+
+use super::{Connectionable, Desult, Dialect, DynRow, Dypes, Error, FromDypes, Params};
+use bind;
+use std;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Table the applied migrations are recorded in
+pub const MIGRATIONS_TABLE: &str = "_dengine_migrations";
+
+#[derive(Clone, Debug)]
+pub struct Migration {
+    pub version: u64,
+    pub name: String,
+    /// None when read from a directory with only dialect variants
+    up: Option<String>,
+    down: Option<String>,
+    /// Dialect name to sql
+    up_variants: HashMap<String, String>,
+    down_variants: HashMap<String, String>,
+}
+
+impl Migration {
+    pub fn new(version: u64, name: &str, up: &str) -> Self {
+        Migration {
+            version,
+            name: name.to_string(),
+            up: Some(up.to_string()),
+            down: None,
+            up_variants: HashMap::new(),
+            down_variants: HashMap::new(),
+        }
+    }
+
+    /// Sql to revert the migration
+    pub fn down(mut self, sql: &str) -> Self {
+        self.down = Some(sql.to_string());
+        self
+    }
+
+    /// Up sql used instead for the dialect named dialect, ex: "sqlite"
+    pub fn up_for(mut self, dialect: &str, sql: &str) -> Self {
+        self.up_variants.insert(dialect.to_string(), sql.to_string());
+        self
+    }
+
+    /// Down sql used instead for the dialect named dialect
+    pub fn down_for(mut self, dialect: &str, sql: &str) -> Self {
+        self.down_variants.insert(dialect.to_string(), sql.to_string());
+        self
+    }
+
+    /// None when there is neither a generic up sql nor one for dialect
+    pub fn up_sql(&self, dialect: &dyn Dialect) -> Option<&str> {
+        match self.up_variants.get(dialect.name()) {
+            Some(x) => Some(x),
+            None => self.up.as_ref().map(|x| x.as_str()),
+        }
+    }
+
+    pub fn down_sql(&self, dialect: &dyn Dialect) -> Option<&str> {
+        match self.down_variants.get(dialect.name()) {
+            Some(x) => Some(x),
+            None => self.down.as_ref().map(|x| x.as_str()),
+        }
+    }
+
+    /// Checksum of the up sql for dialect, ignoring surrounding whitespace
+    pub fn checksum(&self, dialect: &dyn Dialect) -> String {
+        // FNV-1a, stable across builds unlike std's hashers
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for b in self.up_sql(dialect).unwrap_or("").trim().bytes() {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+}
+
+/// A migration recorded as applied
+#[derive(Clone, Debug, Serialize)]
+pub struct AppliedMigration {
+    pub version: u64,
+    pub name: String,
+    pub checksum: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Migrations are sorted by version, versions have to be unique
+    pub fn new(migrations: Vec<Migration>) -> Desult<Self> {
+        let mut migrations = migrations;
+        migrations.sort_by_key(|x| x.version);
+
+        for n in migrations.windows(2) {
+            if n[0].version == n[1].version {
+                return Err(Error::LibErr(format!(
+                    "Duplicate migration version {}",
+                    n[0].version
+                )));
+            }
+        }
+
+        Ok(Migrator { migrations })
+    }
+
+    /// Read migrations from files named `{version}_{name}.{up|down}[.{dialect}].sql`.
+    /// Other files are ignored. Each version needs an up file, a version with only
+    /// dialect up files fails to migrate on other dialects
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Desult<Self> {
+        let mut found: HashMap<u64, Migration> = HashMap::new();
+
+        let entries = std::fs::read_dir(dir).map_err(|e| Error::LibErr(e.to_string()))?;
+        for entry in entries {
+            let path = entry.map_err(|e| Error::LibErr(e.to_string()))?.path();
+            let file_name = match path.file_name().and_then(|x| x.to_str()) {
+                Some(x) if x.ends_with(".sql") => x.to_string(),
+                _ => continue,
+            };
+
+            let parts: Vec<&str> = file_name[..file_name.len() - 4].split('.').collect();
+            let (stem, direction, dialect) = match parts.len() {
+                2 => (parts[0], parts[1], None),
+                3 => (parts[0], parts[1], Some(parts[2])),
+                _ => return Err(bad_file_name(&file_name)),
+            };
+
+            let mut stem = stem.splitn(2, '_');
+            let version: u64 = match stem.next().map(|x| x.parse()) {
+                Some(Ok(x)) => x,
+                _ => return Err(bad_file_name(&file_name)),
+            };
+            let name = stem.next().unwrap_or("");
+
+            let sql = std::fs::read_to_string(&path).map_err(|e| Error::LibErr(e.to_string()))?;
+            let migration = found.remove(&version).unwrap_or_else(|| Migration {
+                up: None,
+                ..Migration::new(version, name, "")
+            });
+
+            let migration = match (direction, dialect) {
+                ("up", None) => Migration {
+                    up: Some(sql),
+                    ..migration
+                },
+                ("down", None) => migration.down(&sql),
+                ("up", Some(d)) => migration.up_for(d, &sql),
+                ("down", Some(d)) => migration.down_for(d, &sql),
+                _ => return Err(bad_file_name(&file_name)),
+            };
+            found.insert(version, migration);
+        }
+
+        for n in found.values() {
+            if n.up.is_none() && n.up_variants.is_empty() {
+                return Err(Error::LibErr(format!(
+                    "Migration {} {} has no up file",
+                    n.version, n.name
+                )));
+            }
+        }
+
+        Self::new(found.into_iter().map(|x| x.1).collect())
+    }
+
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Migrations recorded in the migrations table, in version order
+    pub fn applied<C: Connectionable>(&self, con: &C) -> Desult<Vec<AppliedMigration>> {
+        self.create_table(con)?;
+
+        let sql = format!(
+            "SELECT version, name, checksum FROM {} ORDER BY version",
+            C::quote_ident(MIGRATIONS_TABLE)
+        );
+        let rows: Vec<DynRow> = con.array(&sql, (), false)?;
+        let mode = con.conv_mode();
+
+        rows.into_iter()
+            .map(|row| {
+                let get = |name| row.get_by_name(name).cloned().unwrap_or(Dypes::Null);
+                Ok(AppliedMigration {
+                    version: u64::from_dypes(get("version"), mode)?,
+                    name: String::from_dypes(get("name"), mode)?,
+                    checksum: String::from_dypes(get("checksum"), mode)?,
+                })
+            }).collect()
+    }
+
+    /// Migrations not applied yet, in version order
+    pub fn pending<C: Connectionable>(&self, con: &C) -> Desult<Vec<&Migration>> {
+        let applied = self.verify(con)?;
+        Ok(self
+            .migrations
+            .iter()
+            .filter(|x| !applied.iter().any(|a| a.version == x.version))
+            .collect())
+    }
+
+    /// Apply all pending migrations, returns the applied versions
+    pub fn migrate<C: Connectionable>(&self, con: &C) -> Desult<Vec<u64>> {
+        let dialect = C::dialect();
+        let mut rt = Vec::new();
+
+        for n in self.pending(con)? {
+            let up = match n.up_sql(dialect) {
+                Some(x) => x,
+                None => {
+                    return Err(Error::LibErr(format!(
+                        "Migration {} has no up sql for {}",
+                        n.version,
+                        dialect.name()
+                    )))
+                }
+            };
+
+            let mut queries = statements(up, dialect);
+            queries.push((
+                format!(
+                    "INSERT INTO {} (version, name, checksum) VALUES (?, ?, ?)",
+                    C::quote_ident(MIGRATIONS_TABLE)
+                ),
+                Params::from((n.version, n.name.clone(), n.checksum(dialect))),
+            ));
+
+            con.execute_transaction(queries)?;
+            rt.push(n.version);
+        }
+
+        Ok(rt)
+    }
+
+    /// Revert applied migrations newer than version, newest first.
+    /// Returns the reverted versions
+    pub fn rollback_to<C: Connectionable>(&self, con: &C, version: u64) -> Desult<Vec<u64>> {
+        let dialect = C::dialect();
+        let mut applied = self.verify(con)?;
+        applied.reverse();
+
+        let mut rt = Vec::new();
+
+        for n in applied.iter().filter(|x| x.version > version) {
+            let migration = self.find(n.version)?;
+            let down = match migration.down_sql(dialect) {
+                Some(x) => x,
+                None => {
+                    return Err(Error::LibErr(format!(
+                        "Migration {} has no down sql",
+                        n.version
+                    )))
+                }
+            };
+
+            let mut queries = statements(down, dialect);
+            queries.push((
+                format!(
+                    "DELETE FROM {} WHERE version = ?",
+                    C::quote_ident(MIGRATIONS_TABLE)
+                ),
+                Params::from(n.version),
+            ));
+
+            con.execute_transaction(queries)?;
+            rt.push(n.version);
+        }
+
+        Ok(rt)
+    }
+
+    /// Applied migrations, after checking each still exists with the same checksum
+    fn verify<C: Connectionable>(&self, con: &C) -> Desult<Vec<AppliedMigration>> {
+        let applied = self.applied(con)?;
+
+        for n in &applied {
+            let checksum = self.find(n.version)?.checksum(C::dialect());
+            if checksum != n.checksum {
+                return Err(Error::LibErr(format!(
+                    "Checksum of applied migration {} {} changed from {} to {}",
+                    n.version, n.name, n.checksum, checksum
+                )));
+            }
+        }
+
+        Ok(applied)
+    }
+
+    fn find(&self, version: u64) -> Desult<&Migration> {
+        match self.migrations.iter().find(|x| x.version == version) {
+            Some(x) => Ok(x),
+            None => Err(Error::LibErr(format!(
+                "Applied migration {} is missing",
+                version
+            ))),
+        }
+    }
+
+    fn create_table<C: Connectionable>(&self, con: &C) -> Desult<()> {
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                version BIGINT NOT NULL PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                checksum VARCHAR(64) NOT NULL,
+                applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )",
+            C::quote_ident(MIGRATIONS_TABLE)
+        );
+        con.execute(&sql, ())
+    }
+}
+
+/// Statements of a migration script, without params
+fn statements(sql: &str, dialect: &dyn Dialect) -> Vec<(String, Params)> {
+    bind::split_statements(sql, dialect)
+        .into_iter()
+        .map(|x| (x.to_string(), Params::from(())))
+        .collect()
+}
+
+fn bad_file_name(file_name: &str) -> Error {
+    Error::LibErr(format!(
+        "Migration file {} is not named {{version}}_{{name}}.{{up|down}}[.{{dialect}}].sql",
+        file_name
+    ))
+}
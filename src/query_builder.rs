@@ -0,0 +1,440 @@
+use super::{Connectionable, Desult, Dypes, Error, FromDypes, Params, Queryable, SelectHolder};
+use dialect::OPERATORS;
+use std;
+
+/// Builds select queries that render for the backend they are run on.
+/// Table and column names are validated and quoted, values are always bound as params.
+/// ```ignore
+/// //Untested
+/// let users: Vec<User> = QueryBuilder::new()
+///     .columns(&["user.id", "user.name"])
+///     .from("user")
+///     .left_join("address", "address.user_id", "user.id")
+///     .where_eq("user.active", 1)
+///     .where_in("address.city", vec!["Kochi", "Delhi"])
+///     .order_by_desc("user.id")
+///     .limit(10)
+///     .offset(20)
+///     .array(&con)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct QueryBuilder {
+    columns: Vec<Part>,
+    from: Option<String>,
+    joins: Vec<Join>,
+    wheres: Vec<Cond>,
+    group_by: Vec<String>,
+    having: Vec<Cond>,
+    order_by: Vec<(String, bool)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Ident(String),
+    Raw(String),
+}
+
+#[derive(Clone, Debug)]
+struct Join {
+    kind: &'static str,
+    table: String,
+    left: String,
+    right: String,
+}
+
+#[derive(Clone, Debug)]
+enum Cond {
+    Op(String, String, Dypes),
+    In(String, Vec<Dypes>, bool),
+    Null(String, bool),
+    Raw(String, Params),
+}
+
+impl QueryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Columns to select, `*` and `table.*` are allowed.
+    /// Defaults to * when no columns are given
+    pub fn columns(mut self, colums: &[&str]) -> Self {
+        for n in colums {
+            self.columns.push(Part::Ident(n.to_string()));
+        }
+        self
+    }
+
+    /// Add an expression to the select list as is, ex: "COUNT(*) AS count"
+    pub fn select_raw(mut self, expr: &str) -> Self {
+        self.columns.push(Part::Raw(expr.to_string()));
+        self
+    }
+
+    pub fn from(mut self, table: &str) -> Self {
+        self.from = Some(table.to_string());
+        self
+    }
+
+    /// INNER JOIN table ON left = right
+    pub fn join(self, table: &str, left: &str, right: &str) -> Self {
+        self.add_join("INNER JOIN", table, left, right)
+    }
+
+    /// LEFT JOIN table ON left = right
+    pub fn left_join(self, table: &str, left: &str, right: &str) -> Self {
+        self.add_join("LEFT JOIN", table, left, right)
+    }
+
+    fn add_join(mut self, kind: &'static str, table: &str, left: &str, right: &str) -> Self {
+        self.joins.push(Join {
+            kind,
+            table: table.to_string(),
+            left: left.to_string(),
+            right: right.to_string(),
+        });
+        self
+    }
+
+    /// WHERE colum = val, conditions are joined with AND
+    pub fn where_eq<T>(self, colum: &str, val: T) -> Self
+    where
+        Dypes: std::convert::From<T>,
+    {
+        self.where_op(colum, "=", val)
+    }
+
+    /// WHERE colum op val, op is one of =, !=, <>, <, <=, >, >=, LIKE, NOT LIKE
+    pub fn where_op<T>(mut self, colum: &str, op: &str, val: T) -> Self
+    where
+        Dypes: std::convert::From<T>,
+    {
+        self.wheres
+            .push(Cond::Op(colum.to_string(), op.to_uppercase(), Dypes::from(val)));
+        self
+    }
+
+    /// WHERE colum IN (vals), an empty list matches no rows
+    pub fn where_in<T>(mut self, colum: &str, vals: Vec<T>) -> Self
+    where
+        Dypes: std::convert::From<T>,
+    {
+        let vals = vals.into_iter().map(Dypes::from).collect();
+        self.wheres.push(Cond::In(colum.to_string(), vals, false));
+        self
+    }
+
+    /// WHERE colum NOT IN (vals), an empty list matches all rows
+    pub fn where_not_in<T>(mut self, colum: &str, vals: Vec<T>) -> Self
+    where
+        Dypes: std::convert::From<T>,
+    {
+        let vals = vals.into_iter().map(Dypes::from).collect();
+        self.wheres.push(Cond::In(colum.to_string(), vals, true));
+        self
+    }
+
+    pub fn where_null(mut self, colum: &str) -> Self {
+        self.wheres.push(Cond::Null(colum.to_string(), false));
+        self
+    }
+
+    pub fn where_not_null(mut self, colum: &str) -> Self {
+        self.wheres.push(Cond::Null(colum.to_string(), true));
+        self
+    }
+
+    /// Add a condition as is, with ? or :name placeholders for params
+    pub fn where_raw<P>(mut self, sql: &str, params: P) -> Self
+    where
+        Params: std::convert::From<P>,
+    {
+        self.wheres
+            .push(Cond::Raw(sql.to_string(), Params::from(params)));
+        self
+    }
+
+    pub fn group_by(mut self, colums: &[&str]) -> Self {
+        for n in colums {
+            self.group_by.push(n.to_string());
+        }
+        self
+    }
+
+    /// Add a HAVING condition as is, with ? or :name placeholders for params
+    pub fn having_raw<P>(mut self, sql: &str, params: P) -> Self
+    where
+        Params: std::convert::From<P>,
+    {
+        self.having
+            .push(Cond::Raw(sql.to_string(), Params::from(params)));
+        self
+    }
+
+    pub fn order_by(mut self, colum: &str) -> Self {
+        self.order_by.push((colum.to_string(), false));
+        self
+    }
+
+    pub fn order_by_desc(mut self, colum: &str) -> Self {
+        self.order_by.push((colum.to_string(), true));
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Render the query for the backend C.
+    /// Returns the sql and the params in placeholder order
+    pub fn to_sql<C: Connectionable>(&self) -> Desult<(String, Vec<Dypes>)> {
+        let (mut sql, params) = self.render_body::<C>()?;
+
+        if !self.order_by.is_empty() {
+            let mut order = Vec::new();
+            for (col, desc) in &self.order_by {
+                order.push(format!("{} {}", ident::<C>(col)?, if *desc { "DESC" } else { "ASC" }));
+            }
+            sql += &format!(" ORDER BY {}", order.join(", "));
+        }
+
+        if self.limit.is_some() || self.offset.is_some() {
+            sql += &format!(" {}", C::limit_offset(self.limit, self.offset));
+        }
+
+        Ok((sql, params))
+    }
+
+    /// Everything up to ORDER BY
+    fn render_body<C: Connectionable>(&self) -> Desult<(String, Vec<Dypes>)> {
+        let mut params = Vec::new();
+
+        let from = match self.from {
+            Some(ref x) => x,
+            None => return Err(Error::LibErr("QueryBuilder needs a table, use from".to_string())),
+        };
+
+        let columns: Vec<String> = if self.columns.is_empty() {
+            vec!["*".to_string()]
+        } else {
+            self.columns
+                .iter()
+                .map(|x| match x {
+                    Part::Ident(x) => ident::<C>(x),
+                    Part::Raw(x) => Ok(x.clone()),
+                }).collect::<Desult<_>>()?
+        };
+
+        let mut sql = format!("SELECT {} FROM {}", columns.join(", "), C::dialect().ident(from)?);
+
+        for n in &self.joins {
+            sql += &format!(
+                " {} {} ON {} = {}",
+                n.kind,
+                C::dialect().ident(&n.table)?,
+                ident::<C>(&n.left)?,
+                ident::<C>(&n.right)?
+            );
+        }
+
+        if !self.wheres.is_empty() {
+            sql += &format!(" WHERE {}", render_conds::<C>(&self.wheres, &mut params)?);
+        }
+
+        if !self.group_by.is_empty() {
+            let cols: Vec<String> = self
+                .group_by
+                .iter()
+                .map(|x| ident::<C>(x))
+                .collect::<Desult<_>>()?;
+            sql += &format!(" GROUP BY {}", cols.join(", "));
+        }
+
+        if !self.having.is_empty() {
+            sql += &format!(" HAVING {}", render_conds::<C>(&self.having, &mut params)?);
+        }
+
+        Ok((sql, params))
+    }
+
+    /// Run the query with Connectionable::select.
+    /// If calc_found_rows is true count is set by a COUNT(*) query
+    /// without ORDER BY, LIMIT and OFFSET
+    pub fn select<T, C>(&self, con: &C, calc_found_rows: bool) -> Desult<SelectHolder<T>>
+    where
+        T: Queryable + std::fmt::Debug,
+        C: Connectionable,
+    {
+        let (sql, params) = self.to_sql::<C>()?;
+        let mut res = con.select(&sql, params, false)?;
+
+        if calc_found_rows {
+            res.count = self.count(con)?;
+        }
+
+        Ok(res)
+    }
+
+    pub fn array<T, C>(&self, con: &C) -> Desult<Vec<T>>
+    where
+        T: Queryable + std::fmt::Debug,
+        C: Connectionable,
+    {
+        let (sql, params) = self.to_sql::<C>()?;
+        con.array(&sql, params, false)
+    }
+
+    pub fn row<T, C>(&self, con: &C) -> Desult<T>
+    where
+        T: Queryable,
+        C: Connectionable,
+    {
+        let (sql, params) = self.to_sql::<C>()?;
+        con.row(&sql, params)
+    }
+
+    pub fn value<T, C>(&self, con: &C, colum: &str) -> Desult<T>
+    where
+        T: FromDypes,
+        C: Connectionable,
+    {
+        let (sql, params) = self.to_sql::<C>()?;
+        con.value(&sql, colum, params)
+    }
+
+    /// Number of rows the query returns without LIMIT and OFFSET
+    pub fn count<C: Connectionable>(&self, con: &C) -> Desult<usize> {
+        let (sql, params) = self.render_body::<C>()?;
+        let sql = format!("SELECT COUNT(*) AS count FROM ({}) AS dengine_count", sql);
+        let count: u64 = con.value(&sql, "count", params)?;
+        Ok(count as usize)
+    }
+}
+
+fn render_conds<C: Connectionable>(conds: &[Cond], params: &mut Vec<Dypes>) -> Desult<String> {
+    let mut rt = Vec::new();
+
+    for n in conds {
+        let s = match n {
+            Cond::Op(col, op, val) => {
+                if !OPERATORS.contains(&op.as_str()) {
+                    return Err(Error::LibErr(format!("Invalid operator {}", op)));
+                }
+                params.push(val.clone());
+                format!("{} {} ?", ident::<C>(col)?, op)
+            }
+            Cond::In(_, vals, not) if vals.is_empty() => {
+                if *not {
+                    "1 = 1".to_string()
+                } else {
+                    "1 = 0".to_string()
+                }
+            }
+            Cond::In(col, vals, not) => {
+                params.extend(vals.iter().cloned());
+                format!(
+                    "{} {} ({})",
+                    ident::<C>(col)?,
+                    if *not { "NOT IN" } else { "IN" },
+                    C::dialect().placeholders(vals.len())
+                )
+            }
+            Cond::Null(col, not) => format!(
+                "{} {}",
+                ident::<C>(col)?,
+                if *not { "IS NOT NULL" } else { "IS NULL" }
+            ),
+            Cond::Raw(sql, vals) => {
+                let (sql, vals) = vals.clone().bind(sql, C::dialect())?;
+                params.extend(vals);
+                format!("({})", sql)
+            }
+        };
+        rt.push(s);
+    }
+
+    Ok(rt.join(" AND "))
+}
+
+/// Validate and quote a column name, `*` and `table.*` are allowed
+fn ident<C: Connectionable>(colum: &str) -> Desult<String> {
+    if colum == "*" {
+        return Ok(colum.to_string());
+    }
+
+    if colum.ends_with(".*") {
+        let table = C::dialect().ident(&colum[..colum.len() - 2])?;
+        return Ok(format!("{}.*", table));
+    }
+
+    C::dialect().ident(colum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {my_sql, sqlite};
+
+    fn users() -> QueryBuilder {
+        QueryBuilder::new()
+            .columns(&["user.*", "address.city"])
+            .from("user")
+            .left_join("address", "address.user_id", "user.id")
+            .where_eq("user.active", 1)
+            .where_in("address.city", vec!["Kochi", "Delhi"])
+            .where_not_in("user.id", Vec::<u64>::new())
+            .where_raw("user.age > ?", 18)
+            .order_by_desc("user.id")
+            .offset(20)
+    }
+
+    fn ints(params: &[Dypes]) -> Vec<i64> {
+        params
+            .iter()
+            .filter_map(|x| match x {
+                Dypes::Int(x) => Some(*x),
+                Dypes::Uint(x) => Some(*x as i64),
+                _ => None,
+            }).collect()
+    }
+
+    #[test]
+    fn renders_per_backend() {
+        let (sql, params) = users().to_sql::<sqlite::Connection>().unwrap();
+        assert_eq!(
+            sql,
+            "SELECT \"user\".*, \"address\".\"city\" FROM \"user\" \
+             LEFT JOIN \"address\" ON \"address\".\"user_id\" = \"user\".\"id\" \
+             WHERE \"user\".\"active\" = ? AND \"address\".\"city\" IN (?,?) AND 1 = 1 \
+             AND (user.age > ?) ORDER BY \"user\".\"id\" DESC LIMIT -1 OFFSET 20"
+        );
+        assert_eq!(params.len(), 4);
+        assert_eq!(ints(&params), vec![1, 18]);
+
+        let (sql, _) = users().limit(10).to_sql::<my_sql::Connection>().unwrap();
+        assert_eq!(
+            sql,
+            "SELECT `user`.*, `address`.`city` FROM `user` \
+             LEFT JOIN `address` ON `address`.`user_id` = `user`.`id` \
+             WHERE `user`.`active` = ? AND `address`.`city` IN (?,?) AND 1 = 1 \
+             AND (user.age > ?) ORDER BY `user`.`id` DESC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_names_and_operators() {
+        let q = QueryBuilder::new().from("user");
+        assert!(q.clone().columns(&["id; DROP"]).to_sql::<sqlite::Connection>().is_err());
+        assert!(q.clone().where_op("id", "OR 1 =", 1).to_sql::<sqlite::Connection>().is_err());
+        assert!(q.clone().order_by("1id").to_sql::<sqlite::Connection>().is_err());
+        assert!(QueryBuilder::new().to_sql::<sqlite::Connection>().is_err());
+    }
+}
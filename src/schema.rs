@@ -0,0 +1,220 @@
+use super::{Dypes, Rnd2};
+
+/// Tables of a database as returned by Connectionable::schema
+/// ```ignore
+/// //Untested
+/// let schema = con.schema().unwrap();
+///
+/// for table in &schema.tables {
+///     println!("{} {:?}", table.qualified_name(), table.primary_key);
+///     for col in &table.columns {
+///         println!("  {} {} nullable: {}", col.name, col.data_type, col.nullable);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    pub tables: Vec<TableSchema>,
+}
+
+impl Schema {
+    /// Table by name, or by schema.name for attached sqlite databases
+    pub fn table(&self, name: &str) -> Option<&TableSchema> {
+        self.tables.iter().find(|x| x.qualified_name() == name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableSchema {
+    /// Attached database the table is in, None for the main database
+    pub schema: Option<String>,
+    pub name: String,
+    pub columns: Vec<ColumnSchema>,
+    /// Primary key columns in key order
+    pub primary_key: Vec<String>,
+    /// Indexes other than the primary key
+    pub indexes: Vec<IndexSchema>,
+    pub foreign_keys: Vec<ForeignKeySchema>,
+}
+
+impl TableSchema {
+    /// schema.name for tables of attached databases, else name
+    pub fn qualified_name(&self) -> String {
+        match self.schema {
+            Some(ref x) => format!("{}.{}", x, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Empty table named by a qualified name, see qualified_name
+    pub(crate) fn named(qualified_name: &str) -> Self {
+        let mut parts = qualified_name.splitn(2, '.');
+        match (parts.next(), parts.next()) {
+            (Some(schema), Some(name)) => TableSchema {
+                schema: Some(schema.to_string()),
+                name: name.to_string(),
+                ..Default::default()
+            },
+            _ => TableSchema {
+                name: qualified_name.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Option<&ColumnSchema> {
+        self.columns.iter().find(|x| x.name == name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    /// Declared type, ex: "int(11)" for mysql or "INTEGER" for sqlite
+    pub data_type: String,
+    pub nullable: bool,
+    /// Default as an sql expression
+    pub default: Option<String>,
+    /// For sqlite true for an INTEGER PRIMARY KEY, which is an alias of the rowid
+    pub auto_increment: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexSchema {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ForeignKeySchema {
+    /// Constraint name, sqlite does not report it
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+    pub ref_table: String,
+    /// Empty when the primary key of ref_table is referenced
+    pub ref_columns: Vec<String>,
+    pub on_update: Option<String>,
+    pub on_delete: Option<String>,
+}
+
+/// Backend independent column type, see Dialect::sql_type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlType {
+    Bool,
+    Int,
+    Uint,
+    BigInt,
+    BigUint,
+    Float,
+    Double,
+    /// DECIMAL and NUMERIC, exchanged as text so no precision is lost
+    Decimal,
+    /// DATE, TIME, DATETIME and TIMESTAMP, exchanged as text
+    Date,
+    Text,
+    Bytes,
+}
+
+impl SqlType {
+    /// Type of a declared colum type, ex: "int(10) unsigned" to Uint.
+    /// None for types without a mapping, like GEOMETRY or sqlite colums without a type
+    pub fn from_decl(decl: &str) -> Option<SqlType> {
+        let t = decl.trim().to_lowercase();
+        let unsigned = t.contains("unsigned");
+
+        if t.starts_with("tinyint(1)") || t.starts_with("bool") {
+            Some(SqlType::Bool)
+        } else if t.starts_with("year") {
+            Some(SqlType::Int)
+        } else if t.contains("int") && !t.contains("point") {
+            // sqlite integers are 64 bit
+            let big = t.starts_with("bigint") || t == "integer";
+            Some(match (big, unsigned) {
+                (true, true) => SqlType::BigUint,
+                (true, false) => SqlType::BigInt,
+                (false, true) => SqlType::Uint,
+                (false, false) => SqlType::Int,
+            })
+        } else if t.starts_with("double") || t.starts_with("real") {
+            Some(SqlType::Double)
+        } else if t.starts_with("float") {
+            Some(SqlType::Float)
+        } else if ["decimal", "numeric", "dec", "fixed"].iter().any(|x| t.starts_with(x)) {
+            Some(SqlType::Decimal)
+        } else if t.starts_with("date") || t.starts_with("time") {
+            Some(SqlType::Date)
+        } else if t.contains("blob") || t.contains("binary") {
+            Some(SqlType::Bytes)
+        } else if ["char", "text", "clob"].iter().any(|x| t.contains(x))
+            || ["enum", "set", "json"].iter().any(|x| t.starts_with(x))
+        {
+            Some(SqlType::Text)
+        } else {
+            None
+        }
+    }
+}
+
+/// Rust types usable as fields of a derive(Table) struct
+pub trait FieldType {
+    fn sql_type() -> SqlType;
+
+    /// Only Option<T> is nullable
+    fn nullable() -> bool {
+        false
+    }
+}
+
+macro_rules! impl_field_type {
+    ($t:ty, $s:ident) => {
+        impl FieldType for $t {
+            fn sql_type() -> SqlType {
+                SqlType::$s
+            }
+        }
+    };
+}
+
+impl_field_type!(bool, Bool);
+impl_field_type!(i32, Int);
+impl_field_type!(u32, Uint);
+impl_field_type!(i64, BigInt);
+impl_field_type!(u64, BigUint);
+impl_field_type!(f32, Float);
+impl_field_type!(f64, Double);
+impl_field_type!(Rnd2, Double);
+impl_field_type!(String, Text);
+impl_field_type!(Vec<u8>, Bytes);
+
+impl<T: FieldType> FieldType for Option<T> {
+    fn sql_type() -> SqlType {
+        T::sql_type()
+    }
+
+    fn nullable() -> bool {
+        true
+    }
+}
+
+/// Text of an introspection value, None for NULL
+pub(crate) fn dypes_str(val: Option<&Dypes>) -> Option<String> {
+    match val {
+        Some(Dypes::String(x)) => Some(x.clone()),
+        Some(Dypes::Bytes(x)) => Some(String::from_utf8_lossy(x).into_owned()),
+        Some(Dypes::Uint(x)) => Some(x.to_string()),
+        Some(Dypes::Int(x)) => Some(x.to_string()),
+        Some(Dypes::Float(x)) => Some(x.to_string()),
+        _ => None,
+    }
+}
+
+/// Integer of an introspection value, 0 for NULL
+pub(crate) fn dypes_int(val: Option<&Dypes>) -> i64 {
+    match val {
+        Some(Dypes::Uint(x)) => *x as i64,
+        Some(Dypes::Int(x)) => *x,
+        Some(x) => dypes_str(Some(x)).and_then(|x| x.parse().ok()).unwrap_or(0),
+        None => 0,
+    }
+}
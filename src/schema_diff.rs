@@ -0,0 +1,335 @@
+//! Differences between two schemas, ex: a live database and the tables
+//! derived from structs, and the statements that apply them.
+//! ```ignore
+//! //Untested
+//! let live = con.schema().unwrap();
+//! let expected = Schema {
+//!     tables: vec![User::table_schema(DbCon::dialect())],
+//! };
+//!
+//! let diff = live.diff(&expected);
+//! println!("{}", diff);
+//!
+//! for sql in diff.alter_sql(DbCon::dialect()).unwrap() {
+//!     con.execute(&sql, ()).unwrap();
+//! }
+//! ```
+
+use super::{ColumnSchema, Desult, Dialect, ForeignKeySchema, IndexSchema, Schema, TableSchema};
+use std;
+
+/// A change turning one schema into another, tables are named by qualified name
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum SchemaChange {
+    AddTable(TableSchema),
+    DropTable(String),
+    AddColumn(String, ColumnSchema),
+    DropColumn(String, String),
+    /// Table, old and new definition of the colum
+    AlterColumn(String, ColumnSchema, ColumnSchema),
+    /// Table, old and new primary key colums
+    AlterPrimaryKey(String, Vec<String>, Vec<String>),
+    AddIndex(String, IndexSchema),
+    DropIndex(String, String),
+    AddForeignKey(String, ForeignKeySchema),
+    DropForeignKey(String, ForeignKeySchema),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Statements applying the changes in order, see Dialect::alter_sql.
+    /// Fails on a change the dialect cannot apply in place
+    pub fn alter_sql(&self, dialect: &dyn Dialect) -> Desult<Vec<String>> {
+        let mut rt = Vec::new();
+        for n in &self.changes {
+            rt.extend(dialect.alter_sql(n)?);
+        }
+        Ok(rt)
+    }
+}
+
+impl Schema {
+    /// Changes turning self into to, tables are matched by qualified name
+    pub fn diff(&self, to: &Schema) -> SchemaDiff {
+        let mut changes = Vec::new();
+
+        for n in &to.tables {
+            match self.table(&n.qualified_name()) {
+                Some(x) => changes.extend(x.diff(n).changes),
+                None => changes.push(SchemaChange::AddTable(n.clone())),
+            }
+        }
+
+        for n in &self.tables {
+            let name = n.qualified_name();
+            if to.table(&name).is_none() {
+                changes.push(SchemaChange::DropTable(name));
+            }
+        }
+
+        SchemaDiff { changes }
+    }
+}
+
+impl TableSchema {
+    /// Changes turning self into to, ignoring the table names.
+    /// Types are compared case insensitive and without integer display widths,
+    /// foreign keys are matched by their colums
+    pub fn diff(&self, to: &TableSchema) -> SchemaDiff {
+        let table = self.qualified_name();
+        let mut changes = Vec::new();
+
+        // Drops first so a changed index or key can be added back under the same name
+        for n in &self.foreign_keys {
+            if !to.foreign_keys.iter().any(|x| same_foreign_key(n, x)) {
+                changes.push(SchemaChange::DropForeignKey(table.clone(), n.clone()));
+            }
+        }
+
+        for n in &self.indexes {
+            if !to.indexes.iter().any(|x| same_index(n, x)) {
+                changes.push(SchemaChange::DropIndex(table.clone(), n.name.clone()));
+            }
+        }
+
+        for n in &self.columns {
+            if to.column(&n.name).is_none() {
+                changes.push(SchemaChange::DropColumn(table.clone(), n.name.clone()));
+            }
+        }
+
+        for n in &to.columns {
+            match self.column(&n.name) {
+                Some(x) if !same_column(self, x, to, n) => {
+                    changes.push(SchemaChange::AlterColumn(table.clone(), x.clone(), n.clone()))
+                }
+                Some(_) => (),
+                None => changes.push(SchemaChange::AddColumn(table.clone(), n.clone())),
+            }
+        }
+
+        if self.primary_key != to.primary_key {
+            changes.push(SchemaChange::AlterPrimaryKey(
+                table.clone(),
+                self.primary_key.clone(),
+                to.primary_key.clone(),
+            ));
+        }
+
+        for n in &to.indexes {
+            if !self.indexes.iter().any(|x| same_index(x, n)) {
+                changes.push(SchemaChange::AddIndex(table.clone(), n.clone()));
+            }
+        }
+
+        for n in &to.foreign_keys {
+            if !self.foreign_keys.iter().any(|x| same_foreign_key(x, n)) {
+                changes.push(SchemaChange::AddForeignKey(table.clone(), n.clone()));
+            }
+        }
+
+        SchemaDiff { changes }
+    }
+}
+
+fn same_column(
+    a_table: &TableSchema,
+    a: &ColumnSchema,
+    b_table: &TableSchema,
+    b: &ColumnSchema,
+) -> bool {
+    normalize_type(&a.data_type) == normalize_type(&b.data_type)
+        && a.nullable == b.nullable
+        && normalize_default(&a.default) == normalize_default(&b.default)
+        && is_auto_increment(a_table, a) == is_auto_increment(b_table, b)
+}
+
+/// A single INTEGER primary key of sqlite always is an alias of the rowid
+fn is_auto_increment(table: &TableSchema, colum: &ColumnSchema) -> bool {
+    colum.auto_increment
+        || (table.primary_key == [colum.name.as_str()]
+            && normalize_type(&colum.data_type) == "integer")
+}
+
+fn same_index(a: &IndexSchema, b: &IndexSchema) -> bool {
+    a.name == b.name && a.columns == b.columns && a.unique == b.unique
+}
+
+/// Keys are the same when their colums and references match.
+/// An empty ref_columns references the primary key and matches any
+fn same_foreign_key(a: &ForeignKeySchema, b: &ForeignKeySchema) -> bool {
+    a.columns == b.columns
+        && a.ref_table == b.ref_table
+        && (a.ref_columns == b.ref_columns || a.ref_columns.is_empty() || b.ref_columns.is_empty())
+        && normalize_action(&a.on_update) == normalize_action(&b.on_update)
+        && normalize_action(&a.on_delete) == normalize_action(&b.on_delete)
+}
+
+/// Lowercase with single spaces and without integer display widths,
+/// ex: "INT(11) UNSIGNED" to "int unsigned"
+fn normalize_type(data_type: &str) -> String {
+    let data_type = data_type
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    for n in &["tinyint", "smallint", "mediumint", "bigint", "integer", "int"] {
+        if data_type.starts_with(n) && data_type[n.len()..].starts_with('(') {
+            if let Some(end) = data_type.find(')') {
+                return format!("{}{}", n, &data_type[end + 1..]);
+            }
+        }
+    }
+
+    data_type
+}
+
+/// Default without surrounding parentheses, a NULL default is no default.
+/// Quoted literals are kept as is, other expressions are lowercased
+fn normalize_default(default: &Option<String>) -> Option<String> {
+    let mut rt = match default {
+        Some(x) => x.trim(),
+        None => return None,
+    };
+
+    while rt.len() >= 2 && rt.starts_with('(') && rt.ends_with(')') {
+        rt = rt[1..rt.len() - 1].trim();
+    }
+
+    if rt.len() >= 2 && rt.starts_with('\'') && rt.ends_with('\'') {
+        Some(rt.to_string())
+    } else if rt.eq_ignore_ascii_case("null") {
+        None
+    } else {
+        Some(rt.to_lowercase())
+    }
+}
+
+/// NO ACTION and RESTRICT are the default
+fn normalize_action(action: &Option<String>) -> Option<String> {
+    match action.as_ref().map(|x| x.trim().to_uppercase()) {
+        Some(ref x) if x == "NO ACTION" || x == "RESTRICT" => None,
+        x => x,
+    }
+}
+
+fn describe_column(colum: &ColumnSchema) -> String {
+    let mut rt = colum.data_type.clone();
+    if !colum.nullable {
+        rt += " NOT NULL";
+    }
+    if let Some(ref x) = colum.default {
+        rt += &format!(" DEFAULT {}", x);
+    }
+    if colum.auto_increment {
+        rt += " AUTO_INCREMENT";
+    }
+    rt
+}
+
+fn describe_foreign_key(fk: &ForeignKeySchema) -> String {
+    let mut rt = format!(
+        "({}) -> {} ({})",
+        fk.columns.join(", "),
+        fk.ref_table,
+        fk.ref_columns.join(", ")
+    );
+    if let Some(ref x) = fk.on_delete {
+        rt += &format!(" ON DELETE {}", x);
+    }
+    if let Some(ref x) = fk.on_update {
+        rt += &format!(" ON UPDATE {}", x);
+    }
+    rt
+}
+
+impl std::fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SchemaChange::AddTable(x) => write!(f, "+ table {}", x.qualified_name()),
+            SchemaChange::DropTable(x) => write!(f, "- table {}", x),
+            SchemaChange::AddColumn(t, x) => {
+                write!(f, "+ column {}.{} {}", t, x.name, describe_column(x))
+            }
+            SchemaChange::DropColumn(t, x) => write!(f, "- column {}.{}", t, x),
+            SchemaChange::AlterColumn(t, a, b) => write!(
+                f,
+                "~ column {}.{} {} -> {}",
+                t,
+                a.name,
+                describe_column(a),
+                describe_column(b)
+            ),
+            SchemaChange::AlterPrimaryKey(t, a, b) => write!(
+                f,
+                "~ primary key {} ({}) -> ({})",
+                t,
+                a.join(", "),
+                b.join(", ")
+            ),
+            SchemaChange::AddIndex(t, x) => write!(
+                f,
+                "+ {}index {}.{} ({})",
+                if x.unique { "unique " } else { "" },
+                t,
+                x.name,
+                x.columns.join(", ")
+            ),
+            SchemaChange::DropIndex(t, x) => write!(f, "- index {}.{}", t, x),
+            SchemaChange::AddForeignKey(t, x) => {
+                write!(f, "+ foreign key {} {}", t, describe_foreign_key(x))
+            }
+            SchemaChange::DropForeignKey(t, x) => {
+                write!(f, "- foreign key {} {}", t, describe_foreign_key(x))
+            }
+        }
+    }
+}
+
+/// One change per line
+impl std::fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for n in &self.changes {
+            writeln!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_type_drops_int_widths() {
+        assert_eq!(normalize_type("INT(11)"), "int");
+        assert_eq!(normalize_type("int(10)  UNSIGNED"), "int unsigned");
+        assert_eq!(normalize_type("bigint(20) unsigned"), "bigint unsigned");
+        assert_eq!(normalize_type("TINYINT(1)"), "tinyint");
+        assert_eq!(normalize_type("VARCHAR(64)"), "varchar(64)");
+        assert_eq!(normalize_type("decimal(10,2)"), "decimal(10,2)");
+    }
+
+    #[test]
+    fn normalize_default_strips_parentheses() {
+        let d = |x: &str| normalize_default(&Some(x.to_string()));
+
+        assert_eq!(d("'active'"), Some("'active'".to_string()));
+        assert_eq!(d("((0))"), Some("0".to_string()));
+        assert_eq!(d("( 'A' )"), Some("'A'".to_string()));
+        assert_ne!(d("'A'"), d("'a'"));
+        assert_eq!(d("CURRENT_TIMESTAMP"), Some("current_timestamp".to_string()));
+        assert_eq!(d("NULL"), None);
+        assert_eq!(d("'NULL'"), Some("'NULL'".to_string()));
+        assert_eq!(normalize_default(&None), None);
+    }
+}
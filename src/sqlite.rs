@@ -1,10 +1,15 @@
 use super::{
-    chrono, deslite, round2, Affected, Connectionable, Desult, Dypes, Error, Insertable, Params,
-    Queryable, Rnd2, Row, Rowable, SelectHolder,
+    chrono, deslite, round2, Affected, ColumnMeta, ColumnSchema, Connectionable, ConvMode, Desult,
+    Dialect, DynRow, Dypes, Error, ForeignKeySchema, FromDypes, In, IndexSchema, Insertable, Params,
+    Queryable, Rnd2, Row, Rowable, Schema, SelectHolder, SqliteDialect, TableSchema,
 };
 use deslite::{SqliteCon, Stmt, Value};
+use libsqlite3_sys as ffi;
+use schema::{dypes_int, dypes_str};
 use std;
 use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
 
 impl From<deslite::Error> for Error {
     fn from(val: deslite::Error) -> Self {
@@ -25,45 +30,161 @@ pub struct Connection {
     db_name: String,
     pub con: deslite::SqliteCon,
     attached: Vec<String>,
+    conv_mode: ConvMode,
 }
 
 impl Connection {
     pub fn new(db_name: &str) -> Desult<Self> {
-        let con = SqliteCon::new(db_name).map_err(|e| Error::from(e))?;
+        let con = SqliteCon::new(db_name).map_err(Error::from)?;
 
         Ok(Connection {
             db_name: db_name.to_string(),
             con,
             attached: Vec::new(),
+            conv_mode: ConvMode::Strict,
         })
     }
 
+    /// Set how numeric values of returned rows are converted.
+    /// ConvMode::Lossy restores the old `as` cast behaviour
+    pub fn set_conv_mode(&mut self, mode: ConvMode) {
+        self.conv_mode = mode;
+    }
+
+    /// Version of the sqlite library, ex: (3, 35, 5)
+    pub fn version(&self) -> Desult<(u32, u32, u32)> {
+        let version: String = self.value("SELECT sqlite_version() AS version", "version", ())?;
+        let mut parts = version.split('.').map(|x| x.parse::<u32>().unwrap_or(0));
+
+        Ok((
+            parts.next().unwrap_or(0),
+            parts.next().unwrap_or(0),
+            parts.next().unwrap_or(0),
+        ))
+    }
+
     pub fn attach(&mut self, db_name: &str, db_as: &str) -> Desult<()> {
         let mut stmt = Stmt::init(&self.con);
-        let sql = format!("ATTACH DATABASE ? AS {}", db_as);
-        stmt.prepare(sql.as_str()).map_err(|e| Error::from(e))?;
+        let sql = format!("ATTACH DATABASE ? AS {}", Self::dialect().ident(db_as)?);
+        stmt.prepare(sql.as_str()).map_err(Error::from)?;
         let params = vec![db_name];
         stmt.bind_values(&params).unwrap();
-        stmt.execute().map_err(|e| Error::from(e))?;
+        stmt.execute().map_err(Error::from)?;
         self.attached.push(db_as.to_string());
         Ok(())
     }
-}
 
-impl From<Dypes> for deslite::Value {
-    fn from(x: Dypes) -> deslite::Value {
-        use deslite::Value;
-        match x {
-            Dypes::Uint(x) => Value::Uint(x),
-            Dypes::Int(x) => Value::Int(x),
-            Dypes::Float(x) => Value::Float(x),
-            Dypes::String(x) => Value::String(x),
-            Dypes::Bytes(x) => Value::Bytes(x),
-            Dypes::Null => Value::Null,
+    /// Describe a table with the table_info, index_list and foreign_key_list pragmas
+    fn table_schema(&self, schema: Option<String>, name: String) -> Desult<TableSchema> {
+        let db = Self::quote_ident(schema.as_ref().map(|x| x.as_str()).unwrap_or("main"));
+        let pragma = |x: &str, arg: &str| -> Desult<Vec<DynRow>> {
+            let sql = format!("PRAGMA {}.{}({})", db, x, Self::quote_ident(arg));
+            self.array(&sql, (), false)
+        };
+
+        let mut columns = Vec::new();
+        let mut primary_key = Vec::new();
+        for n in pragma("table_info", &name)? {
+            let col_name = dypes_str(n.get_by_name("name")).unwrap_or_default();
+            let pk = dypes_int(n.get_by_name("pk"));
+            if pk > 0 {
+                primary_key.push((pk, col_name.clone()));
+            }
+
+            columns.push(ColumnSchema {
+                name: col_name,
+                data_type: dypes_str(n.get_by_name("type")).unwrap_or_default(),
+                nullable: dypes_int(n.get_by_name("notnull")) == 0,
+                default: dypes_str(n.get_by_name("dflt_value")),
+                auto_increment: false,
+            });
         }
+        primary_key.sort();
+        let primary_key: Vec<String> = primary_key.into_iter().map(|x| x.1).collect();
+
+        // A single INTEGER PRIMARY KEY is an alias of the rowid
+        if primary_key.len() == 1 {
+            if let Some(x) = columns.iter_mut().find(|x| x.name == primary_key[0]) {
+                if x.data_type.eq_ignore_ascii_case("INTEGER") {
+                    x.auto_increment = true;
+                    x.nullable = false;
+                }
+            }
+        }
+
+        let mut indexes = Vec::new();
+        for n in pragma("index_list", &name)? {
+            if dypes_str(n.get_by_name("origin")) == Some("pk".to_string()) {
+                continue;
+            }
+
+            let index_name = dypes_str(n.get_by_name("name")).unwrap_or_default();
+            let index_columns = pragma("index_info", &index_name)?
+                .into_iter()
+                .filter_map(|x| dypes_str(x.get_by_name("name")))
+                .collect();
+
+            indexes.push(IndexSchema {
+                name: index_name,
+                columns: index_columns,
+                unique: dypes_int(n.get_by_name("unique")) != 0,
+            });
+        }
+
+        let mut foreign_keys: Vec<(i64, ForeignKeySchema)> = Vec::new();
+        for n in pragma("foreign_key_list", &name)? {
+            let id = dypes_int(n.get_by_name("id"));
+            if !foreign_keys.iter().any(|x| x.0 == id) {
+                foreign_keys.push((
+                    id,
+                    ForeignKeySchema {
+                        name: None,
+                        columns: Vec::new(),
+                        ref_table: dypes_str(n.get_by_name("table")).unwrap_or_default(),
+                        ref_columns: Vec::new(),
+                        on_update: dypes_str(n.get_by_name("on_update")),
+                        on_delete: dypes_str(n.get_by_name("on_delete")),
+                    },
+                ));
+            }
+
+            let fk = &mut foreign_keys.iter_mut().find(|x| x.0 == id).unwrap().1;
+            fk.columns.push(dypes_str(n.get_by_name("from")).unwrap_or_default());
+            if let Some(x) = dypes_str(n.get_by_name("to")) {
+                fk.ref_columns.push(x);
+            }
+        }
+        foreign_keys.sort_by_key(|x| x.0);
+
+        Ok(TableSchema {
+            schema,
+            name,
+            columns,
+            primary_key,
+            indexes,
+            foreign_keys: foreign_keys.into_iter().map(|x| x.1).collect(),
+        })
     }
 }
 
+/// Values of the params returned by Params::bind.
+/// Lists have no sqlite value, Params::bind expands them
+fn to_values(values: Vec<Dypes>) -> Desult<Vec<Value>> {
+    values
+        .into_iter()
+        .map(|x| match x {
+            Dypes::Uint(x) => Ok(Value::Uint(x)),
+            Dypes::Int(x) => Ok(Value::Int(x)),
+            Dypes::Float(x) => Ok(Value::Float(x)),
+            Dypes::String(x) => Ok(Value::String(x)),
+            Dypes::Bytes(x) => Ok(Value::Bytes(x)),
+            Dypes::Null => Ok(Value::Null),
+            Dypes::List(_) => Err(Error::LibErr(
+                "A list param can only be bound to a single ? placeholder".to_string(),
+            )),
+        }).collect()
+}
+
 impl From<Value> for Dypes {
     fn from(x: Value) -> Dypes {
         match x {
@@ -117,40 +238,138 @@ impl<'a> Rowable for deslite::Row<'a> {
     }
 }
 
+/// A row with the columns of its statement
+struct SqliteRow<'a, 'b: 'a> {
+    row: &'a deslite::Row<'b>,
+    columns: &'a [ColumnMeta],
+}
+
+impl<'a, 'b> Rowable for SqliteRow<'a, 'b> {
+    fn get_val(&self, key: &str) -> Option<Dypes> {
+        self.row.get_val(key)
+    }
+
+    /// deslite does not check the index, sqlite reads NULL past the last column
+    fn get_val_at(&self, index: usize) -> Option<Dypes> {
+        if index >= self.columns.len() {
+            return None;
+        }
+
+        self.row.get_value(index).ok().map(Dypes::from)
+    }
+
+    fn column_meta(&self) -> Vec<ColumnMeta> {
+        self.columns.to_vec()
+    }
+
+    fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    fn get_date_string(&self, key: &str, format: &str) -> Desult<String> {
+        self.row.get_date_string(key, format)
+    }
+}
+
+/// Columns of a prepared statement. deslite only reports the names,
+/// declared type and table of origin are read from sqlite
+fn column_meta(stmt: &Stmt) -> Vec<ColumnMeta> {
+    (0..stmt.colum_count())
+        .map(|i| ColumnMeta {
+            name: stmt.colum_name(i).to_string(),
+            decl_type: c_str(unsafe { ffi::sqlite3_column_decltype(stmt.stmt, i as c_int) }),
+            nullable: None,
+            table: c_str(unsafe { ffi::sqlite3_column_table_name(stmt.stmt, i as c_int) }),
+        }).collect()
+}
+
+/// Copy of a string owned by sqlite, None for NULL
+fn c_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned())
+    }
+}
+
 impl Connectionable for Connection {
+    fn conv_mode(&self) -> ConvMode {
+        self.conv_mode
+    }
+
     fn execute<P>(&self, sql: &str, params: P) -> Desult<()>
     where
         P: std::clone::Clone,
         Params: std::convert::From<P>,
     {
+        let (sql, params) = Params::from(params).bind(sql, Self::dialect())?;
         let mut stmt = deslite::Stmt::init(&self.con);
-        stmt.prepare(sql).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
 
-        let params = Params::from(params);
-        stmt.bind_values(&params.0).map_err(|e| Error::from(e))?;
+        stmt.bind_values(&to_values(params)?).map_err(Error::from)?;
 
-        stmt.execute().map_err(|e| Error::from(e))?;
+        stmt.execute().map_err(Error::from)?;
 
         Ok(())
     }
 
+    fn execute_transaction(&self, queries: Vec<(String, Params)>) -> Desult<()> {
+        self.execute("BEGIN", ())?;
+
+        for (sql, params) in queries {
+            if let Err(e) = self.execute(&sql, params) {
+                self.execute("ROLLBACK", ())?;
+                return Err(e);
+            }
+        }
+
+        self.execute("COMMIT", ())
+    }
+
+    fn for_each_row<T, P, F>(&self, sql: &str, params: P, mut f: F) -> Desult<Vec<ColumnMeta>>
+    where
+        T: Queryable,
+        P: std::clone::Clone,
+        Params: std::convert::From<P>,
+        F: FnMut(T) -> Desult<()>,
+    {
+        let (sql, params) = Params::from(params).bind(sql, Self::dialect())?;
+        let mut stmt = deslite::Stmt::init(&self.con);
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(params)?).map_err(Error::from)?;
+
+        // Stepped by hand as the row iterator of deslite ends quietly on a step error
+        let columns = column_meta(&stmt);
+        loop {
+            let row = match stmt.get_row() {
+                Ok(x) => x,
+                Err(deslite::Error::Empty) => break,
+                Err(e) => return Err(Error::from(e)),
+            };
+            let row = SqliteRow {
+                row: &row,
+                columns: &columns,
+            };
+            f(T::try_new(Row::with_mode(&row, self.conv_mode))?)?;
+        }
+
+        Ok(columns)
+    }
+
     fn value<T, R>(&self, sql: &str, colum: &str, params: R) -> Desult<T>
     where
-        T: std::convert::From<Dypes>,
+        T: FromDypes,
         R: std::clone::Clone,
         Params: std::convert::From<R>,
     {
+        let (sql, params) = Params::from(params).bind(sql, Self::dialect())?;
         let mut stmt = deslite::Stmt::init(&self.con);
-        stmt.prepare(sql).map_err(|e| Error::from(e))?;
-        let params = Params::from(params);
-        stmt.bind_values(&params.0).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(params)?).map_err(Error::from)?;
 
-        let row: deslite::Row = stmt.get_row().map_err(|e| {
-            println!("{:?}", e);
-            Error::from(e)
-        })?;
+        let row: deslite::Row = stmt.get_row().map_err(Error::from)?;
 
-        Ok(T::from(row.get::<Dypes, &str>(colum).unwrap()))
+        T::from_dypes(row.get::<Dypes, &str>(colum).unwrap(), self.conv_mode)
     }
 
     fn select<T: Queryable + std::fmt::Debug, P: std::clone::Clone>(
@@ -162,25 +381,29 @@ impl Connectionable for Connection {
     where
         Params: std::convert::From<P>,
     {
+        let (sql, params) = Params::from(params).bind(sql, Self::dialect())?;
+        let params = to_values(params)?;
         let mut stmt = deslite::Stmt::init(&self.con);
-        stmt.prepare(sql).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
 
-        let params = Params::from(params);
-        stmt.bind_values(&params.0).map_err(|e| Error::from(e))?;
+        stmt.bind_values(&params).map_err(Error::from)?;
 
+        let columns = column_meta(&stmt);
         let res: Vec<T> = stmt
             .get_rows()
             .iter()
-            .map(|row| T::new(Row::new(&row)))
-            .collect();
+            .map(|row| {
+                let row = SqliteRow {
+                    row: &row,
+                    columns: &columns,
+                };
+                T::try_new(Row::with_mode(&row, self.conv_mode))
+            }).collect::<Desult<Vec<T>>>()?;
 
         let res_len = res.len();
 
         if !calc_found_rows {
-            return Ok(SelectHolder {
-                data: res,
-                count: res_len,
-            });
+            Ok(SelectHolder::with_columns(res, res_len, columns))
         } else {
             let start_index = match sql.find(" from ") {
                 Some(x) => x,
@@ -197,8 +420,8 @@ impl Connectionable for Connection {
             let new_sql = new_sql + &sql[start_index..end_index];
 
             let mut stmt = deslite::Stmt::init(&self.con);
-            stmt.prepare(&new_sql).map_err(|e| Error::from(e))?;
-            stmt.bind_values(&params.0).map_err(|e| Error::from(e))?;
+            stmt.prepare(&new_sql).map_err(Error::from)?;
+            stmt.bind_values(&params).map_err(Error::from)?;
 
             let count: Vec<usize> = stmt
                 .get_rows()
@@ -207,15 +430,9 @@ impl Connectionable for Connection {
                 .collect();
 
             match count.get(0) {
-                Some(x) => {
-                    println!("inside get ",);
-                    return Ok(SelectHolder {
-                        data: res,
-                        count: *x,
-                    });
-                }
-                None => return Err(Error::LibErr("Count get error".to_string())),
-            };
+                Some(x) => Ok(SelectHolder::with_columns(res, *x, columns)),
+                None => Err(Error::LibErr("Count get error".to_string())),
+            }
         }
     }
 
@@ -225,61 +442,45 @@ impl Connectionable for Connection {
         R: std::clone::Clone,
         Params: std::convert::From<R>,
     {
+        let (sql, params) = Params::from(params).bind(sql, Self::dialect())?;
         let mut stmt = deslite::Stmt::init(&self.con);
-        stmt.prepare(sql).map_err(|e| Error::from(e))?;
-        let params = Params::from(params);
-        stmt.bind_values(&params.0).map_err(|e| Error::from(e))?;
-
-        let row: deslite::Row = stmt.get_row().map_err(|e| {
-            println!("{:?}", e);
-            Error::from(e)
-        })?;
-
-        Ok(T::new(Row::new(&row)))
-    }
-
-    fn insert_update<T: Insertable>(&self, _table: &str, _fields: Vec<T>) -> Desult<Affected> {
-        unimplemented!()
-        /*
-        let mut c_arr = Vec::new();
-        let mut q_arr = Vec::new();
-        let mut a_arr = Vec::new();
-        let data_fields = T::fields();
-        let mut q_str: String = "".to_string();
-        for (i, n) in fields.iter().enumerate() {
-            q_arr.push(Vec::new());
-            let values = n.values();
-            for (j, m) in data_fields.iter().enumerate() {
-                if i == 0 {
-                    c_arr.push(m.to_string());
-                }
-                q_arr[i].push("?");
-                a_arr.push(values[j].to_string());
-            }
-            if i != 0 {
-                q_str.push(',');
-            }
-            q_str.push_str(format!("({})", q_arr[i].join(&",")).as_str());
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(params)?).map_err(Error::from)?;
+
+        let columns = column_meta(&stmt);
+        let row: deslite::Row = stmt.get_row().map_err(Error::from)?;
+
+        let row = SqliteRow {
+            row: &row,
+            columns: &columns,
+        };
+        T::try_new(Row::with_mode(&row, self.conv_mode))
+    }
+
+    /// Needs sqlite 3.35 for ON CONFLICT DO UPDATE without a conflict target
+    fn insert_update<T: Insertable>(&self, table: &str, fields: Vec<T>) -> Desult<Affected> {
+        let version = self.version()?;
+        if version < (3, 35, 0) {
+            return Err(Error::LibErr(format!(
+                "insert_update needs sqlite 3.35, the library is {}.{}.{}",
+                version.0, version.1, version.2
+            )));
         }
 
-        let sql = format!(
-            "INSERT INTO {} ({}) VALUES {} ON DUPLICATE KEY UPDATE {}",
-            table,
-            c_arr.join(&","),
-            q_str,
-            Self::gen_dupdate(c_arr)
-        );
+        let c_arr = T::fields();
+        let a_arr: Vec<Dypes> = fields.iter().fold(Vec::new(), |mut acc, x| {
+            acc.append(&mut x.values());
+            acc
+        });
+
+        let sql = Self::dialect().upsert_sql(table, &c_arr, &[], fields.len())?;
+        let (sql, a_arr) = Params::new(a_arr).bind(&sql, Self::dialect())?;
 
         let mut stmt = deslite::Stmt::init(&self.con);
-        println!("sql is {}", sql);
-        println!("params is {:?}", a_arr);
-        stmt.prepare(&sql).map_err(|e| Error::from(e))?;
-        stmt.bind_values(&a_arr).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(a_arr)?).map_err(Error::from)?;
 
-        stmt.execute().map_err(|e| {
-            println!("{:?}", e);
-            Error::from(e)
-        })?;
+        stmt.execute().map_err(Error::from)?;
 
         let res = Affected {
             affected_rows: self.con.affected_rows() as u64,
@@ -287,7 +488,6 @@ impl Connectionable for Connection {
         };
 
         Ok(res)
-        */
     }
 
     fn insert<T: Insertable>(&self, table: &str, fields: Vec<T>) -> Desult<Affected> {
@@ -296,28 +496,16 @@ impl Connectionable for Connection {
             acc.append(&mut x.values());
             acc
         });
-        let q_arr: Vec<String> = fields.iter().fold(Vec::new(), |mut acc, _| {
-            let single: Vec<&str> = std::iter::repeat("?").take(colum_names.len()).collect();
-            acc.push(format!("({})", single.join(",")));
-            acc
-        });
-        let sql = format!(
-            "INSERT INTO {} ({}) VALUES {}",
-            table,
-            colum_names.join(","),
-            q_arr.join(",")
-        );
+        let sql = Self::dialect().insert_sql(table, &colum_names, fields.len())?;
+        let (sql, values) = Params::new(values).bind(&sql, Self::dialect())?;
 
         let mut stmt = deslite::Stmt::init(&self.con);
-        stmt.prepare(&sql).map_err(|e| Error::from(e))?;
-        stmt.bind_values(&values).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(values)?).map_err(Error::from)?;
 
         
 
-        stmt.execute().map_err(|e| {
-            println!("{:?}", e);
-            Error::from(e)
-        })?;
+        stmt.execute().map_err(Error::from)?;
 
         let res = Affected {
             affected_rows: self.con.affected_rows() as u64,
@@ -340,31 +528,21 @@ impl Connectionable for Connection {
 
         let colum_names: Vec<String> = T::fields();
 
-        let vars: Vec<String> = colum_names.iter().map(|x| format!("{} = ?", x)).collect();
-
-        let where_str: Vec<String> = where_fields.keys().map(|key| format!("{}?", key)).collect();
+        let where_keys: Vec<&str> = where_fields.keys().cloned().collect();
+        let sql = Self::dialect().update_sql(table, &colum_names, &where_keys)?;
 
         for value in where_fields.values() {
             values.push(value.clone());
         }
 
         drop(where_fields);
-
-        let sql = format!(
-            "UPDATE {} SET {} WHERE {}",
-            table,
-            vars.join(","),
-            where_str.join(" and ")
-        );
+        let (sql, values) = Params::new(values).bind(&sql, Self::dialect())?;
 
         let mut stmt = deslite::Stmt::init(&self.con);
-        stmt.prepare(&sql).map_err(|e| Error::from(e))?;
-        stmt.bind_values(&values).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(values)?).map_err(Error::from)?;
 
-        stmt.execute().map_err(|e| {
-            println!("{:?}", e);
-            Error::from(e)
-        })?;
+        stmt.execute().map_err(Error::from)?;
 
         Ok(Affected {
             affected_rows: self.con.affected_rows() as u64,
@@ -383,42 +561,15 @@ impl Connectionable for Connection {
         T: std::clone::Clone,
         Dypes: std::convert::From<T>,
     {
-        let id_values: Vec<deslite::Value> = id_values
-            .into_iter()
-            .map(|x| deslite::Value::from(Dypes::from(x)))
-            .collect();
-        //let params = mysql::Params::from(params);
-        let mut c_arr: Vec<char> =
-            Vec::with_capacity(std::mem::size_of::<char>() * id_values.len());
-
-        for _ in &id_values {
-            c_arr.push('?');
-        }
-
-        let sql = format!(
-            "delete from {} where {} {} ({})",
-            table,
-            id_colum,
-            in_out,
-            c_arr
-                .iter()
-                .enumerate()
-                .map(|e| if e.0 != 0 {
-                    format!(",{}", e.1)
-                } else {
-                    e.1.to_string()
-                }).collect::<String>()
-        );
+        let sql = Self::dialect().delete_in_sql(table, id_colum, in_out)?;
+        let (sql, id_values) = Params::from(In(id_values)).bind(&sql, Self::dialect())?;
 
         let mut stmt = deslite::Stmt::init(&self.con);
 
-        stmt.prepare(&sql).map_err(|e| Error::from(e))?;
-        stmt.bind_values(&id_values).map_err(|e| Error::from(e))?;
+        stmt.prepare(&sql).map_err(Error::from)?;
+        stmt.bind_values(&to_values(id_values)?).map_err(Error::from)?;
 
-        stmt.execute().map_err(|e| {
-            println!("{:?}", e);
-            Error::from(e)
-        })?;
+        stmt.execute().map_err(Error::from)?;
 
         let res = Affected {
             affected_rows: self.con.affected_rows() as u64,
@@ -444,18 +595,33 @@ impl Connectionable for Connection {
         self.delete_ids::<T>(table, id_colum, id_values, "NOT IN")
     }
 
-    fn concat_colums(colums: Vec<&str>) -> String {
-        let mut s = "(".to_string();
-
-        for (i, n) in colums.into_iter().enumerate() {
-            if i != 0 {
-                s += " || ";
+    fn schema(&self) -> Desult<Schema> {
+        let mut dbs = vec!["main".to_string()];
+        dbs.extend(self.attached.iter().cloned());
+
+        let mut tables = Vec::new();
+        for db in dbs {
+            let sql = format!(
+                "SELECT name FROM {}.sqlite_master
+                WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+                Self::quote_ident(&db)
+            );
+            let names: Vec<String> = self.column(&sql, "name", ())?;
+
+            for name in names {
+                let schema = if db == "main" { None } else { Some(db.clone()) };
+                tables.push(self.table_schema(schema, name)?);
             }
-            s += n;
         }
 
-        s += " )";
+        Ok(Schema { tables })
+    }
+
+    fn concat_colums(colums: Vec<&str>) -> String {
+        Self::dialect().concat(&colums)
+    }
 
-        s
+    fn dialect() -> &'static dyn Dialect {
+        &SqliteDialect
     }
 }
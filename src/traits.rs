@@ -1,35 +1,166 @@
-use super::{Affected, Desult, Dypes, Params, SelectHolder};
+use super::{
+    csv, de, dump, jsonl, serde, Affected, ColumnMeta, ConvMode, CsvOptions, Desult, Dialect,
+    DumpOptions, DynRow, Dypes, Error, FromDypes, ImportReport, In, JsonlOptions, Params, Rnd2,
+    Schema, SelectHolder, TableSchema,
+};
 use std;
 use std::collections::HashMap;
 extern crate chrono;
 
 /// A row of returned data
-pub struct Row<'a>(&'a Rowable);
+pub struct Row<'a> {
+    row: &'a dyn Rowable,
+    mode: ConvMode,
+}
 
 impl<'a> Row<'a> {
     pub fn new<T>(row: &'a T) -> Self
     where
         T: Rowable,
     {
-        Row(row)
+        Self::with_mode(row, ConvMode::Strict)
+    }
+
+    /// Row whose numeric conversions follow mode
+    pub fn with_mode<T>(row: &'a T, mode: ConvMode) -> Self
+    where
+        T: Rowable,
+    {
+        Row { row, mode }
     }
 
     pub fn get<T>(&self, key: &str) -> Option<T>
     where
-        Option<T>: std::convert::From<Dypes>,
+        T: FromDypes,
+    {
+        self.try_get(key).ok()
+    }
+
+    /// Get value of column key.
+    /// Returns ConversionErr if the value does not fit T
+    pub fn try_get<T>(&self, key: &str) -> Desult<T>
+    where
+        T: FromDypes,
+    {
+        self.get_with_mode(key, self.mode)
+    }
+
+    /// Same as try_get but always converts with `as` casts
+    pub fn try_get_lossy<T>(&self, key: &str) -> Desult<T>
+    where
+        T: FromDypes,
     {
-        if key == "user_profile_id" {
-            println!("get user_profile_id {:?}", self.0.get_val(key));
+        self.get_with_mode(key, ConvMode::Lossy)
+    }
+
+    /// Get value of column at index, starting from 0
+    pub fn get_at<T>(&self, index: usize) -> Option<T>
+    where
+        T: FromDypes,
+    {
+        self.try_get_at(index).ok()
+    }
+
+    pub fn try_get_at<T>(&self, index: usize) -> Desult<T>
+    where
+        T: FromDypes,
+    {
+        match self.row.get_val_at(index) {
+            Some(x) => convert(x, self.mode, &index.to_string()),
+            None => Err(Error::IndexOutOfBound(format!(
+                "Column index {} out of {}",
+                index,
+                self.len()
+            ))),
         }
+    }
+
+    /// Looks up key as a column name.
+    /// If there is no such column and key is of the form table.column
+    /// the column is found with the table of origin reported by the driver
+    fn get_with_mode<T>(&self, key: &str, mode: ConvMode) -> Desult<T>
+    where
+        T: FromDypes,
+    {
+        let val = match self.row.get_val(key) {
+            Some(x) => Some(x),
+            None => match key.find('.') {
+                Some(i) => self
+                    .row
+                    .column_index(&key[..i], &key[i + 1..])
+                    .and_then(|x| self.row.get_val_at(x)),
+                None => None,
+            },
+        };
 
-        match self.0.get_val(key) {
-            Some(x) => <Option<T>>::from(x),
-            None => None,
+        match val {
+            Some(x) => convert(x, mode, key),
+            None => Err(Error::IndexOutOfBound(format!("No column named {}", key))),
         }
     }
 
     pub fn get_date_string(&self, key: &str, format: &str) -> Desult<String> {
-        self.0.get_date_string(key, format)
+        self.row.get_date_string(key, format)
+    }
+
+    /// Build a Queryable out of the columns starting with prefix,
+    /// the prefix is stripped from the column names.
+    /// Used for `#[dengine(flatten, prefix = "addr_")]`
+    pub fn nested<T>(&self, prefix: &str) -> Desult<T>
+    where
+        T: Queryable,
+    {
+        let row = Prefixed {
+            row: self.row,
+            prefix,
+        };
+        T::try_new(Row::with_mode(&row, self.mode))
+    }
+
+    /// Same as nested but returns None when all prefixed columns are NULL,
+    /// as they are for a LEFT JOIN without a match
+    pub fn nested_opt<T>(&self, prefix: &str) -> Desult<Option<T>>
+    where
+        T: Queryable,
+    {
+        let row = Prefixed {
+            row: self.row,
+            prefix,
+        };
+
+        let all_null = (0..row.len()).all(|i| match row.get_val_at(i) {
+            Some(Dypes::Null) | None => true,
+            _ => false,
+        });
+
+        if all_null {
+            Ok(None)
+        } else {
+            T::try_new(Row::with_mode(&row, self.mode)).map(Some)
+        }
+    }
+
+    /// Column names in select order
+    pub fn columns(&self) -> Vec<String> {
+        self.row.column_names()
+    }
+
+    /// Column names with type and table of origin if reported by the driver
+    pub fn column_meta(&self) -> Vec<ColumnMeta> {
+        self.row.column_meta()
+    }
+
+    /// Number of columns
+    pub fn len(&self) -> usize {
+        self.row.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn conv_mode(&self) -> ConvMode {
+        self.mode
     }
 
     /*
@@ -75,9 +206,72 @@ impl<'a> Row<'a> {
     */
 }
 
+/// View of the columns of a row starting with prefix
+struct Prefixed<'a> {
+    row: &'a dyn Rowable,
+    prefix: &'a str,
+}
+
+impl<'a> Prefixed<'a> {
+    /// Index in the underlying row of the nth prefixed column
+    fn inner_index(&self, index: usize) -> Option<usize> {
+        self.row
+            .column_names()
+            .iter()
+            .enumerate()
+            .filter(|x| x.1.starts_with(self.prefix))
+            .nth(index)
+            .map(|x| x.0)
+    }
+}
+
+impl<'a> Rowable for Prefixed<'a> {
+    fn get_val(&self, key: &str) -> Option<Dypes> {
+        self.row.get_val(&format!("{}{}", self.prefix, key))
+    }
+
+    fn get_val_at(&self, index: usize) -> Option<Dypes> {
+        self.inner_index(index).and_then(|x| self.row.get_val_at(x))
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.row
+            .column_names()
+            .into_iter()
+            .filter(|x| x.starts_with(self.prefix))
+            .map(|x| x[self.prefix.len()..].to_string())
+            .collect()
+    }
+
+    fn column_meta(&self) -> Vec<ColumnMeta> {
+        self.row
+            .column_meta()
+            .into_iter()
+            .filter(|x| x.name.starts_with(self.prefix))
+            .map(|mut x| {
+                x.name = x.name[self.prefix.len()..].to_string();
+                x
+            }).collect()
+    }
+
+    fn get_date_string(&self, key: &str, format: &str) -> Desult<String> {
+        self.row.get_date_string(&format!("{}{}", self.prefix, key), format)
+    }
+}
+
+fn convert<T>(val: Dypes, mode: ConvMode, column: &str) -> Desult<T>
+where
+    T: FromDypes,
+{
+    T::from_dypes(val, mode).map_err(|e| match e {
+        Error::ConversionErr(e) => Error::ConversionErr(format!("{}: {}", column, e)),
+        e => e,
+    })
+}
+
 /// Sructs for holding a row of select data
 /// must imlement this trait.
-/// ```
+/// ```ignore
 /// struct User{
 ///     id: u64,
 ///     name: String,
@@ -94,10 +288,159 @@ impl<'a> Row<'a> {
 /// ```
 pub trait Queryable {
     fn new(row: Row) -> Self;
+
+    /// Same as new but returns the error of a missing column or failed conversion.
+    /// Used by select and the other queries, defaults to new
+    fn try_new(row: Row) -> Desult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self::new(row))
+    }
+}
+
+/// Scalars read the first column of the row.
+/// try_new fails when the row has no columns or the value does not convert to the type
+macro_rules! impl_queryable_for_scalar {
+    ($($t:ty),*) => (
+        $(
+            impl Queryable for $t {
+                fn new(row: Row) -> Self {
+                    Self::try_new(row).unwrap()
+                }
+
+                fn try_new(row: Row) -> Desult<Self> {
+                    row.try_get_at(0)
+                }
+            }
+        )*
+    );
+}
+
+impl_queryable_for_scalar!(u64, i64, u32, i32, f64, f32, bool, String, Rnd2, Dypes);
+
+/// Reads the first column of the row, None for NULL.
+/// try_new fails when the row has no columns or the value does not convert to T
+impl<T> Queryable for Option<T>
+where
+    T: FromDypes,
+{
+    fn new(row: Row) -> Self {
+        Self::try_new(row).unwrap()
+    }
+
+    fn try_new(row: Row) -> Desult<Self> {
+        row.try_get_at(0)
+    }
+}
+
+/// Tuples read columns by position.
+/// try_new fails when the row has too few columns or a value does not convert to its type
+macro_rules! impl_queryable_for_tuple {
+    ($([$A:ident, $i:expr]),*) => (
+        impl<$($A,)*> Queryable for ($($A,)*) where $($A: FromDypes,)* {
+            fn new(row: Row) -> Self {
+                Self::try_new(row).unwrap()
+            }
+
+            fn try_new(row: Row) -> Desult<Self> {
+                Ok(($(row.try_get_at::<$A>($i)?,)*))
+            }
+        }
+    );
+}
+
+impl_queryable_for_tuple!([A, 0]);
+impl_queryable_for_tuple!([A, 0], [B, 1]);
+impl_queryable_for_tuple!([A, 0], [B, 1], [C, 2]);
+impl_queryable_for_tuple!([A, 0], [B, 1], [C, 2], [D, 3]);
+impl_queryable_for_tuple!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4]);
+impl_queryable_for_tuple!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5]);
+impl_queryable_for_tuple!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6]);
+impl_queryable_for_tuple!(
+    [A, 0],
+    [B, 1],
+    [C, 2],
+    [D, 3],
+    [E, 4],
+    [F, 5],
+    [G, 6],
+    [H, 7]
+);
+impl_queryable_for_tuple!(
+    [A, 0],
+    [B, 1],
+    [C, 2],
+    [D, 3],
+    [E, 4],
+    [F, 5],
+    [G, 6],
+    [H, 7],
+    [I, 8]
+);
+impl_queryable_for_tuple!(
+    [A, 0],
+    [B, 1],
+    [C, 2],
+    [D, 3],
+    [E, 4],
+    [F, 5],
+    [G, 6],
+    [H, 7],
+    [I, 8],
+    [J, 9]
+);
+impl_queryable_for_tuple!(
+    [A, 0],
+    [B, 1],
+    [C, 2],
+    [D, 3],
+    [E, 4],
+    [F, 5],
+    [G, 6],
+    [H, 7],
+    [I, 8],
+    [J, 9],
+    [K, 10]
+);
+impl_queryable_for_tuple!(
+    [A, 0],
+    [B, 1],
+    [C, 2],
+    [D, 3],
+    [E, 4],
+    [F, 5],
+    [G, 6],
+    [H, 7],
+    [I, 8],
+    [J, 9],
+    [K, 10],
+    [L, 11]
+);
+
+/// Structs with has many relations.
+/// derive(Queryable) implements it when a field has
+/// `#[dengine(has_many = "table", fk = "colum")]`
+/// ```ignore
+/// //Untested
+/// #[derive(Debug, Queryable)]
+/// struct User {
+///     id: u64,
+///     #[dengine(has_many = "address", fk = "user_id")]
+///     addresses: Vec<Address>,
+/// }
+///
+/// let mut users: Vec<User> = con.array("SELECT * FROM user", (), false).unwrap();
+/// con.load_has_many(&mut users).unwrap();
+/// ```
+pub trait HasMany: Queryable + Sized {
+    /// Fill the has many fields of parents, one query per relation
+    fn load_has_many<C: Connectionable>(parents: &mut [Self], con: &C) -> Desult<()>;
 }
 
 ///Need to implement for structs to be inserted
 ///
+/// derive(Insertable) leaves out has_many, flatten and skip fields
 pub trait Insertable {
     ///List of fields of struct as &str
     fn fields() -> Vec<String>;
@@ -106,12 +449,77 @@ pub trait Insertable {
     fn values(&self) -> Vec<Dypes>;
 }
 
+/// Struct that maps to a table, use #[derive(Table)].
+/// Field types are mapped to column types per backend and Option fields are nullable.
+/// Colum attributes are primary_key, auto_increment, unique, index, nullable,
+/// default = "sql" and sql_type = "sql" to override the mapped type.
+/// unique and index can take a name to index several colums together,
+/// skip leaves a field out
+/// ```ignore
+/// //Untested
+/// #[derive(Table)]
+/// #[dengine(table = "user")]
+/// struct User {
+///     #[dengine(primary_key, auto_increment)]
+///     id: u64,
+///     #[dengine(unique)]
+///     email: String,
+///     #[dengine(index = "user_name", sql_type = "VARCHAR(64)")]
+///     last_name: String,
+///     #[dengine(index = "user_name", sql_type = "VARCHAR(64)")]
+///     first_name: String,
+///     #[dengine(default = "0")]
+///     active: bool,
+///     parent_id: Option<u64>,
+/// }
+///
+/// con.create_table::<User>().unwrap();
+/// ```
+pub trait Table {
+    fn table_name() -> String;
+
+    /// The table with colum types of dialect
+    fn table_schema(dialect: &dyn Dialect) -> TableSchema;
+
+    /// CREATE TABLE followed by a CREATE INDEX per index
+    fn create_table_sql(dialect: &dyn Dialect) -> Desult<Vec<String>> {
+        dialect.create_table_sql(&Self::table_schema(dialect))
+    }
+}
+
 /// Trait that mysql::Row or sqlite::Row
 /// usable inside a dengine::Row
 pub trait Rowable {
     ///Get value from a row
     fn get_val(&self, key: &str) -> Option<Dypes>;
 
+    /// Get value at column index, None when not implemented
+    fn get_val_at(&self, _index: usize) -> Option<Dypes> {
+        None
+    }
+
+    /// Names of the columns in the row, in select order
+    fn column_names(&self) -> Vec<String> {
+        self.column_meta().into_iter().map(|x| x.name).collect()
+    }
+
+    /// Metadata of the columns in the row, in select order. Empty when not implemented
+    fn column_meta(&self) -> Vec<ColumnMeta> {
+        Vec::new()
+    }
+
+    /// Number of columns
+    fn len(&self) -> usize {
+        self.column_names().len()
+    }
+
+    /// Index of column in table, using the table of origin in column_meta
+    fn column_index(&self, table: &str, column: &str) -> Option<usize> {
+        self.column_meta()
+            .iter()
+            .position(|x| x.name == column && x.table.as_ref().map(|t| t == table) == Some(true))
+    }
+
     /// Get date string for row with column name -> key
     /// Format uses chrono format str
     fn get_date_string(&self, key: &str, format: &str) -> Desult<String>;
@@ -120,14 +528,22 @@ pub trait Rowable {
 /// Makes a mysql or sqlite con
 /// usable with dengine apis
 pub trait Connectionable {
+    /// How numeric values of returned rows are converted
+    fn conv_mode(&self) -> ConvMode;
+
     /// Executes a query with params
     fn execute<P>(&self, sql: &str, params: P) -> Desult<()>
     where
         P: std::clone::Clone,
         Params: std::convert::From<P>;
 
+    /// Executes queries in a single transaction, rolled back when one of them fails.
+    /// Mysql commits implicitly on DDL statements like CREATE TABLE, and sets
+    /// FOREIGN_KEY_CHECKS back to its value before the transaction when one fails
+    fn execute_transaction(&self, queries: Vec<(String, Params)>) -> Desult<()>;
+
     /// Select sql query
-    /// params- Individual value or Vec or Tuple
+    /// params- Individual value or Vec or Tuple for ? placeholders, or named_params! for :name
     /// calc_found_rows- If true count parameter in return structure is set to total number of calculated rows. Else return the number of rows returned
     fn select<T: Queryable + std::fmt::Debug, P: std::clone::Clone>(
         &self,
@@ -143,10 +559,27 @@ pub trait Connectionable {
     /// params: Params as in select
     fn value<T, R>(&self, sql: &str, colum: &str, params: R) -> Desult<T>
     where
-        T: std::convert::From<Dypes>,
+        T: FromDypes,
         R: std::clone::Clone,
         Params: std::convert::From<R>;
 
+    /// Call f with each row of a query as it is read, without collecting the rows.
+    /// Stops at the first error of f. Returns the result set columns
+    /// ```ignore
+    /// //Untested
+    /// let mut total = 0;
+    /// con.for_each_row("SELECT amount FROM payment", (), |amount: u64| {
+    ///     total += amount;
+    ///     Ok(())
+    /// }).unwrap();
+    /// ```
+    fn for_each_row<T, P, F>(&self, sql: &str, params: P, f: F) -> Desult<Vec<ColumnMeta>>
+    where
+        T: Queryable,
+        P: std::clone::Clone,
+        Params: std::convert::From<P>,
+        F: FnMut(T) -> Desult<()>;
+
     /// Return a single row
     /// params: Same as in select
     fn row<T, R>(&self, sql: &str, params: R) -> Desult<T>
@@ -168,11 +601,129 @@ pub trait Connectionable {
         self.select(sql, params, calc_found_rows).map(|r| r.data)
     }
 
+    /// Return the values of column colum from every row
+    /// ```ignore
+    /// //Untested
+    /// let ids: Vec<u64> = con.column("SELECT id, name FROM user", "id", ()).unwrap();
+    /// ```
+    fn column<T, P>(&self, sql: &str, colum: &str, params: P) -> Desult<Vec<T>>
+    where
+        T: FromDypes,
+        P: std::clone::Clone,
+        Params: std::convert::From<P>,
+    {
+        let mode = self.conv_mode();
+        self.array::<DynRow, P>(sql, params, false)?
+            .into_iter()
+            .map(|row| match row.get_by_name(colum) {
+                Some(x) => convert(x.clone(), mode, colum),
+                None => Err(Error::IndexOutOfBound(format!("No column named {}", colum))),
+            }).collect()
+    }
+
+    /// Select rows of table whose fk_colum is one of keys with a single
+    /// `IN (...)` query and group them by fk_colum
+    /// ```ignore
+    /// //Untested
+    /// let users: Vec<User> = con.array("SELECT * FROM user", (), false).unwrap();
+    /// let ids = users.iter().map(|x| x.id).collect();
+    /// let addresses: HashMap<u64, Vec<Address>> =
+    ///     con.children_by_key("address", "user_id", ids).unwrap();
+    /// ```
+    fn children_by_key<T, K>(
+        &self,
+        table: &str,
+        fk_colum: &str,
+        keys: Vec<K>,
+    ) -> Desult<HashMap<K, Vec<T>>>
+    where
+        T: Queryable,
+        K: FromDypes + std::cmp::Eq + std::hash::Hash + std::clone::Clone,
+        Dypes: std::convert::From<K>,
+    {
+        let mut rt: HashMap<K, Vec<T>> = HashMap::new();
+
+        let mut keys = keys;
+        let mut seen = std::collections::HashSet::new();
+        keys.retain(|x| seen.insert(x.clone()));
+
+        if keys.is_empty() {
+            return Ok(rt);
+        }
+
+        let sql = format!(
+            "SELECT * FROM {} WHERE {} IN (?)",
+            Self::dialect().ident(table)?,
+            Self::dialect().ident(fk_colum)?
+        );
+
+        let mode = self.conv_mode();
+        let rows: Vec<DynRow> = self.array(&sql, In(keys), false)?;
+
+        for row in rows {
+            let key = match row.get_by_name(fk_colum) {
+                Some(x) => convert::<K>(x.clone(), mode, fk_colum)?,
+                None => {
+                    return Err(Error::IndexOutOfBound(format!(
+                        "No column named {}",
+                        fk_colum
+                    )))
+                }
+            };
+            let child = T::try_new(Row::with_mode(&row, mode))?;
+            rt.entry(key).or_default().push(child);
+        }
+
+        Ok(rt)
+    }
+
+    /// Fill the `#[dengine(has_many = ...)]` fields of parents
+    fn load_has_many<T>(&self, parents: &mut [T]) -> Desult<()>
+    where
+        T: HasMany,
+        Self: Sized,
+    {
+        T::load_has_many(parents, self)
+    }
+
+    /// Select rows into any type implementing serde::Deserialize.
+    /// Struct fields are looked up by their (serde renamed) name,
+    /// a nested struct field `address` is read from the columns prefixed with `address_`.
+    /// ```ignore
+    /// //Untested
+    /// #[derive(Deserialize)]
+    /// struct Address {
+    ///     city: String,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     id: u64,
+    ///     #[serde(rename = "user_name")]
+    ///     name: String,
+    ///     address: Option<Address>,
+    /// }
+    ///
+    /// let sql = "SELECT u.id, u.user_name, a.city AS address_city FROM user u LEFT JOIN address a ON a.user_id = u.id";
+    /// let users: Vec<User> = con.select_de(sql, ()).unwrap();
+    /// ```
+    fn select_de<T, P>(&self, sql: &str, params: P) -> Desult<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        P: std::clone::Clone,
+        Params: std::convert::From<P>,
+    {
+        self.array::<de::De<T>, P>(sql, params, false)?
+            .into_iter()
+            .map(|x| x.0)
+            .collect()
+    }
+
     /// Not tested
     fn insert_update<T: Insertable>(&self, table: &str, fields: Vec<T>) -> Desult<Affected>;
 
     /// Insert a vector of structs into a table.
-    /// ```
+    /// ```ignore
     /// //Untested
     /// #[derive(Debug, Insertable)]
     /// struct User{
@@ -198,7 +749,9 @@ pub trait Connectionable {
     ///
     fn insert<T: Insertable>(&self, table: &str, fields: Vec<T>) -> Desult<Affected>;
 
-    /// Update fields of a table where where_fields.key = where_fields.value
+    /// Update fields of a table where where_fields.key = where_fields.value.
+    /// A key can name an operator after the colum, ex: "age >= ".
+    /// Table and colum names are validated and quoted, see validate_ident
     fn update<T: Insertable>(
         &self,
         table: &str,
@@ -206,12 +759,9 @@ pub trait Connectionable {
         where_fields: HashMap<&str, Dypes>,
     ) -> Desult<Affected>;
 
+    /// Upsert clause for the backend, see Dialect::upsert_clause
     fn gen_dupdate(colums: Vec<String>) -> String {
-        let mut rt = Vec::new();
-        for n in colums {
-            rt.push(format!("{} = VALUES({}) ", n, n));
-        }
-        rt.join(&",")
+        Self::dialect().upsert_clause(&colums, &[])
     }
 
     /// Delete rows
@@ -237,4 +787,133 @@ pub trait Connectionable {
         Dypes: std::convert::From<T>;
 
     fn concat_colums(colums: Vec<&str>) -> String;
+
+    /// Tables, columns, indexes and foreign keys of the database.
+    /// Sqlite includes the tables of attached databases
+    fn schema(&self) -> Desult<Schema>;
+
+    /// Create the table of T and its indexes, see Table
+    fn create_table<T: Table>(&self) -> Desult<()> {
+        for n in T::create_table_sql(Self::dialect())? {
+            self.execute(&n, ())?;
+        }
+        Ok(())
+    }
+
+    /// Write the rows of a query as csv with a header row, returns the number of rows.
+    /// NULL is written as an empty field, bytes that are not utf8 as base64.
+    /// Fields are separated by options.delimiter
+    /// ```ignore
+    /// //Untested
+    /// let file = std::fs::File::create("user.csv").unwrap();
+    /// let rows = con.export_csv("SELECT * FROM user", (), file, &CsvOptions::default()).unwrap();
+    /// ```
+    fn export_csv<P, W>(&self, sql: &str, params: P, writer: W, options: &CsvOptions) -> Desult<u64>
+    where
+        P: std::clone::Clone,
+        Params: std::convert::From<P>,
+        W: std::io::Write,
+        Self: Sized,
+    {
+        csv::export_csv(self, sql, params, writer, options)
+    }
+
+    /// Insert the records of a csv with a header row into table.
+    /// Header names are matched to colums ignoring case and values are converted
+    /// to the colum types, see CsvOptions
+    /// ```ignore
+    /// //Untested
+    /// let file = std::fs::File::open("user.csv").unwrap();
+    /// let report = con.import_csv("user", file, &CsvOptions::default()).unwrap();
+    /// ```
+    fn import_csv<R>(&self, table: &str, reader: R, options: &CsvOptions) -> Desult<ImportReport>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        csv::import_csv(self, table, reader, options)
+    }
+
+    /// Write the rows of a query as JSON Lines, an object per row, returns the number of rows.
+    /// Rows are streamed to writer, bytes of blob colums and bytes that are not utf8 are base64
+    /// ```ignore
+    /// //Untested
+    /// let file = std::fs::File::create("user.jsonl").unwrap();
+    /// let rows = con.export_jsonl("SELECT * FROM user", (), file).unwrap();
+    /// ```
+    fn export_jsonl<P, W>(&self, sql: &str, params: P, writer: W) -> Desult<u64>
+    where
+        P: std::clone::Clone,
+        Params: std::convert::From<P>,
+        W: std::io::Write,
+        Self: Sized,
+    {
+        jsonl::export_jsonl(self, sql, params, writer)
+    }
+
+    /// Insert the JSON Lines of reader into table in batches.
+    /// Keys are matched to colums ignoring case, strings for blob colums are base64 decoded
+    /// ```ignore
+    /// //Untested
+    /// let file = std::fs::File::open("user.jsonl").unwrap();
+    /// let report = con.import_jsonl("user", file, &JsonlOptions::default()).unwrap();
+    /// ```
+    fn import_jsonl<R>(
+        &self,
+        table: &str,
+        reader: R,
+        options: &JsonlOptions,
+    ) -> Desult<ImportReport>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        jsonl::import_jsonl(self, table, reader, options)
+    }
+
+    /// Write CREATE TABLE, CREATE INDEX and INSERT statements for the tables of the database,
+    /// in the dialect of options.dialect. Referenced tables are written first
+    /// ```ignore
+    /// //Untested
+    /// let file = std::fs::File::create("app.sql").unwrap();
+    /// con.dump(file, &DumpOptions::default()).unwrap();
+    /// ```
+    fn dump<W>(&self, writer: W, options: &DumpOptions) -> Desult<()>
+    where
+        W: std::io::Write,
+        Self: Sized,
+    {
+        dump::dump(self, writer, options)
+    }
+
+    /// Run the statements of a dump in a single transaction, returns the number of statements.
+    /// Mysql commits implicitly on DROP TABLE and CREATE TABLE, so a failed restore of a
+    /// dump with schema on mysql keeps the tables and rows before the failing statement.
+    /// FOREIGN_KEY_CHECKS is set back when a statement fails
+    /// ```ignore
+    /// //Untested
+    /// let file = std::fs::File::open("app.sql").unwrap();
+    /// con.restore(file).unwrap();
+    /// ```
+    fn restore<R>(&self, reader: R) -> Desult<usize>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        dump::restore(self, reader)
+    }
+
+    /// SQL dialect of the backend
+    fn dialect() -> &'static dyn Dialect;
+
+    /// Quote a table or column name for the backend.
+    /// Dotted names like table.colum are quoted per part and * is left as is
+    fn quote_ident(ident: &str) -> String {
+        Self::dialect().quote_ident(ident)
+    }
+
+    /// LIMIT and OFFSET clause for the backend
+    fn limit_offset(limit: Option<u64>, offset: Option<u64>) -> String {
+        Self::dialect().limit_offset(limit, offset)
+    }
 }
@@ -1,5 +1,9 @@
+use super::{Desult, Dialect, Error};
+use bind;
 use serde;
 use std;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 
 #[derive(Clone, Debug)]
 pub enum Dypes {
@@ -9,6 +13,8 @@ pub enum Dypes {
     String(String),
     Bytes(Vec<u8>),
     Null,
+    /// Only valid as a query param, expanded to one placeholder per value, see In
+    List(Vec<Dypes>),
 }
 
 impl From<String> for Dypes {
@@ -17,12 +23,51 @@ impl From<String> for Dypes {
     }
 }
 
+impl From<Vec<u8>> for Dypes {
+    fn from(val: Vec<u8>) -> Self {
+        Dypes::Bytes(val)
+    }
+}
+
+/// None is NULL
+impl<T> From<Option<T>> for Dypes
+where
+    Dypes: std::convert::From<T>,
+{
+    fn from(val: Option<T>) -> Self {
+        match val {
+            Some(x) => Dypes::from(x),
+            None => Dypes::Null,
+        }
+    }
+}
+
 impl<'a> From<&'a str> for Dypes {
     fn from(val: &'a str) -> Self {
         Dypes::String(val.to_string())
     }
 }
 
+/// A list param, expanded to one placeholder per value.
+/// An empty list matches no rows with IN and all rows with NOT IN
+/// ```ignore
+/// //Untested
+/// let users: Vec<User> = con
+///     .array("SELECT * FROM user WHERE id IN (?)", In(vec![1, 2, 3]), false)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct In<T>(pub Vec<T>);
+
+impl<T> From<In<T>> for Dypes
+where
+    Dypes: std::convert::From<T>,
+{
+    fn from(val: In<T>) -> Self {
+        Dypes::List(val.0.into_iter().map(Dypes::from).collect())
+    }
+}
+
 impl From<bool> for Dypes {
     fn from(val: bool) -> Self {
         if val {
@@ -56,16 +101,142 @@ impl From<Dypes> for Option<bool> {
     }
 }
 
-macro_rules! impl_from_dypes_for_opt_int {
-    ($i:ty) => {
+/// How numeric values are converted out of `Dypes`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvMode {
+    /// Fail with `Error::ConversionErr` on truncation, sign loss or overflow
+    Strict,
+    /// Legacy behaviour, values are converted with `as` casts
+    Lossy,
+}
+
+impl Default for ConvMode {
+    fn default() -> Self {
+        ConvMode::Strict
+    }
+}
+
+/// Types that can be read out of a `Dypes` value.
+/// Used by `Row::get`, `Row::try_get` and `Connectionable::value`
+pub trait FromDypes: Sized {
+    fn from_dypes(val: Dypes, mode: ConvMode) -> Desult<Self>;
+}
+
+impl FromDypes for Dypes {
+    fn from_dypes(val: Dypes, _mode: ConvMode) -> Desult<Self> {
+        Ok(val)
+    }
+}
+
+impl<T> FromDypes for Option<T>
+where
+    T: FromDypes,
+{
+    fn from_dypes(val: Dypes, mode: ConvMode) -> Desult<Self> {
+        match val {
+            Dypes::Null => Ok(None),
+            x => T::from_dypes(x, mode).map(Some),
+        }
+    }
+}
+
+impl FromDypes for String {
+    fn from_dypes(val: Dypes, _mode: ConvMode) -> Desult<Self> {
+        match val {
+            Dypes::String(v) => Ok(v),
+            Dypes::Bytes(v) => String::from_utf8(v)
+                .map_err(|e| Error::dypes_conv_err(&Dypes::Bytes(e.into_bytes()), "String")),
+            x => Err(Error::dypes_conv_err(&x, "String")),
+        }
+    }
+}
+
+impl FromDypes for Vec<u8> {
+    fn from_dypes(val: Dypes, _mode: ConvMode) -> Desult<Self> {
+        match val {
+            Dypes::Bytes(v) => Ok(v),
+            Dypes::String(v) => Ok(v.into_bytes()),
+            x => Err(Error::dypes_conv_err(&x, "Vec<u8>")),
+        }
+    }
+}
+
+impl FromDypes for bool {
+    fn from_dypes(val: Dypes, _mode: ConvMode) -> Desult<Self> {
+        match val {
+            Dypes::Int(x) => Ok(x != 0),
+            Dypes::Uint(x) => Ok(x != 0),
+            x => Err(Error::dypes_conv_err(&x, "bool")),
+        }
+    }
+}
+
+macro_rules! impl_from_dypes_for_int {
+    ($i:ident) => {
+        impl FromDypes for $i {
+            fn from_dypes(val: Dypes, mode: ConvMode) -> Desult<Self> {
+                if mode == ConvMode::Lossy {
+                    return match val {
+                        Dypes::Int(x) => Ok(x as $i),
+                        Dypes::Uint(x) => Ok(x as $i),
+                        Dypes::Float(x) => Ok(x as $i),
+                        x => Err(Error::dypes_conv_err(&x, stringify!($i))),
+                    };
+                }
+
+                let res = match val {
+                    Dypes::Int(x) => $i::try_from(x).ok(),
+                    Dypes::Uint(x) => $i::try_from(x).ok(),
+                    // $i::MAX as f64 rounds up to the next power of two for
+                    // 64 bit types, so the upper bound is exclusive
+                    Dypes::Float(x)
+                        if x.fract() == 0.0
+                            && x >= $i::MIN as f64
+                            && x < $i::MAX as f64 + 1.0 =>
+                    {
+                        Some(x as $i)
+                    }
+                    _ => None,
+                };
+
+                res.ok_or_else(|| Error::dypes_conv_err(&val, stringify!($i)))
+            }
+        }
+
         impl From<Dypes> for Option<$i> {
             fn from(val: Dypes) -> Self {
-                match val {
-                    Dypes::Int(x) => Some(x as $i),
-                    Dypes::Uint(x) => Some(x as $i),
-                    Dypes::Float(x) => Some(x as $i),
+                $i::from_dypes(val, ConvMode::Strict).ok()
+            }
+        }
+    };
+}
+
+macro_rules! impl_from_dypes_for_float {
+    ($i:ident, $mantissa:expr) => {
+        impl FromDypes for $i {
+            fn from_dypes(val: Dypes, mode: ConvMode) -> Desult<Self> {
+                let exact = |x: u64| mode == ConvMode::Lossy || x <= 1 << $mantissa;
+                let res = match val {
+                    Dypes::Int(x) if exact(x.unsigned_abs()) => Some(x as $i),
+                    Dypes::Uint(x) if exact(x) => Some(x as $i),
+                    Dypes::Float(x) => {
+                        let y = x as $i;
+                        if mode == ConvMode::Lossy || y.is_finite() || !x.is_finite() {
+                            Some(y)
+                        } else {
+                            None
+                        }
+                    }
                     _ => None,
-                }
+                };
+
+                res.ok_or_else(|| Error::dypes_conv_err(&val, stringify!($i)))
+            }
+        }
+
+        impl From<Dypes> for Option<$i> {
+            fn from(val: Dypes) -> Self {
+                $i::from_dypes(val, ConvMode::Strict).ok()
             }
         }
     };
@@ -108,12 +279,12 @@ impl_to_dypes_for_int!(i32);
 impl_to_dypes_for_float!(f64);
 impl_to_dypes_for_float!(f32);
 
-impl_from_dypes_for_opt_int!(u64);
-impl_from_dypes_for_opt_int!(i64);
-impl_from_dypes_for_opt_int!(u32);
-impl_from_dypes_for_opt_int!(i32);
-impl_from_dypes_for_opt_int!(f64);
-impl_from_dypes_for_opt_int!(f32);
+impl_from_dypes_for_int!(u64);
+impl_from_dypes_for_int!(i64);
+impl_from_dypes_for_int!(u32);
+impl_from_dypes_for_int!(i32);
+impl_from_dypes_for_float!(f64, 53);
+impl_from_dypes_for_float!(f32, 24);
 
 #[derive(Debug)]
 pub struct Rnd2(f64);
@@ -137,24 +308,188 @@ impl serde::Serialize for Rnd2 {
     }
 }
 
-impl From<Dypes> for Option<Rnd2> {
-    fn from(val: Dypes) -> Self {
+/// Bytes are serialized with serialize_bytes (an array of numbers in JSON).
+/// Use `#[serde(with = "dengine::bytes_base64")]` to write them as base64 strings
+impl serde::Serialize for Dypes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Dypes::Uint(x) => serializer.serialize_u64(*x),
+            Dypes::Int(x) => serializer.serialize_i64(*x),
+            Dypes::Float(x) => serializer.serialize_f64(*x),
+            Dypes::String(x) => serializer.serialize_str(x),
+            Dypes::Bytes(x) => serializer.serialize_bytes(x),
+            Dypes::Null => serializer.serialize_none(),
+            Dypes::List(x) => serializer.collect_seq(x),
+        }
+    }
+}
+
+struct DypesVisitor;
+
+impl<'de> serde::de::Visitor<'de> for DypesVisitor {
+    type Value = Dypes;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a number, string, byte array or null")
+    }
+
+    fn visit_bool<E>(self, x: bool) -> Result<Dypes, E> {
+        Ok(Dypes::from(x))
+    }
+
+    fn visit_i64<E>(self, x: i64) -> Result<Dypes, E> {
+        Ok(Dypes::Int(x))
+    }
+
+    fn visit_u64<E>(self, x: u64) -> Result<Dypes, E> {
+        Ok(Dypes::Uint(x))
+    }
+
+    fn visit_f64<E>(self, x: f64) -> Result<Dypes, E> {
+        Ok(Dypes::Float(x))
+    }
+
+    fn visit_str<E>(self, x: &str) -> Result<Dypes, E> {
+        Ok(Dypes::from(x))
+    }
+
+    fn visit_string<E>(self, x: String) -> Result<Dypes, E> {
+        Ok(Dypes::String(x))
+    }
+
+    fn visit_bytes<E>(self, x: &[u8]) -> Result<Dypes, E> {
+        Ok(Dypes::Bytes(x.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, x: Vec<u8>) -> Result<Dypes, E> {
+        Ok(Dypes::Bytes(x))
+    }
+
+    fn visit_none<E>(self) -> Result<Dypes, E> {
+        Ok(Dypes::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Dypes, E> {
+        Ok(Dypes::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Dypes, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Dypes, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut bytes = Vec::new();
+        while let Some(x) = seq.next_element::<u8>()? {
+            bytes.push(x);
+        }
+        Ok(Dypes::Bytes(bytes))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Dypes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DypesVisitor)
+    }
+}
+
+/// Serde helpers writing Dypes::Bytes as a base64 string.
+/// ```ignore
+/// //Untested
+/// #[derive(Serialize, Deserialize)]
+/// struct File {
+///     #[serde(with = "dengine::bytes_base64")]
+///     data: Dypes,
+/// }
+/// ```
+/// Strings are read back as Dypes::Bytes, so the field should only hold bytes or NULL.
+pub mod bytes_base64 {
+    use super::Dypes;
+    use base64;
+    use serde;
+
+    pub fn serialize<S>(val: &Dypes, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
         match val {
-            Dypes::Float(x) => Some(Rnd2::new(x)),
-            _ => None,
+            Dypes::Bytes(x) => serializer.serialize_str(&base64::encode(x)),
+            x => serde::Serialize::serialize(x, serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Dypes, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match serde::Deserialize::deserialize(deserializer)? {
+            Dypes::String(x) => base64::decode(&x)
+                .map(Dypes::Bytes)
+                .map_err(serde::de::Error::custom),
+            x => Ok(x),
         }
     }
 }
 
-pub struct Params(pub Vec<Dypes>);
+impl From<Dypes> for Option<Rnd2> {
+    fn from(val: Dypes) -> Self {
+        Rnd2::from_dypes(val, ConvMode::Strict).ok()
+    }
+}
+
+impl FromDypes for Rnd2 {
+    fn from_dypes(val: Dypes, mode: ConvMode) -> Desult<Self> {
+        f64::from_dypes(val, mode).map(Rnd2::new)
+    }
+}
+
+/// Params of a query, bound to ? placeholders in order.
+/// Params of a map, see named_params!, are bound to :name placeholders by name
+#[derive(Clone, Debug)]
+pub struct Params(pub Vec<Dypes>, Option<HashMap<String, Dypes>>);
 
 impl Params {
     pub fn new(val: Vec<Dypes>) -> Self {
-        Params(val)
+        Params(val, None)
     }
 
+    /// Values in placeholder order, named params are ordered by name.
+    /// Use bind to order named params by the query
     pub fn values(self) -> Vec<Dypes> {
-        self.0
+        match self.1 {
+            None => self.0,
+            Some(x) => {
+                let mut x: Vec<(String, Dypes)> = x.into_iter().collect();
+                x.sort_by(|a, b| a.0.cmp(&b.0));
+                x.into_iter().map(|x| x.1).collect()
+            }
+        }
+    }
+
+    /// Rewrite sql for the params, named params and lists are expanded to ? placeholders.
+    /// Returns the sql and the values in placeholder order
+    pub fn bind(self, sql: &str, dialect: &dyn Dialect) -> Desult<(String, Vec<Dypes>)> {
+        match self.1 {
+            None => bind::bind_positional(sql, dialect, self.0),
+            Some(x) => bind::bind_named(sql, dialect, &x),
+        }
+    }
+}
+
+impl From<HashMap<String, Dypes>> for Params {
+    fn from(x: HashMap<String, Dypes>) -> Self {
+        Params(Vec::new(), Some(x))
     }
 }
 
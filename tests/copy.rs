@@ -0,0 +1,117 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{copy_table, Connectionable, CopyOptions, Dypes};
+
+fn src(key: &str) -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute(
+        &format!(
+            "CREATE TABLE user (id INTEGER NOT NULL{}, name TEXT, updated_at TEXT NOT NULL)",
+            key
+        ),
+        (),
+    ).unwrap();
+    con.execute(
+        "INSERT INTO user (id, name, updated_at) VALUES
+        (1, 'a', '2024-01-01 00:00:00'), (2, 'b', '2024-01-02 00:00:00'),
+        (3, 'c', '2024-01-03 00:00:00')",
+        (),
+    ).unwrap();
+    con
+}
+
+fn names(con: &Connection) -> Vec<(u64, String)> {
+    con.array("SELECT id, name FROM user ORDER BY id", (), false)
+        .unwrap()
+}
+
+fn watermark() -> CopyOptions {
+    CopyOptions {
+        watermark: Some("updated_at".to_string()),
+        chunk_size: 2,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn creates_and_fills_the_table() {
+    let src = src(" PRIMARY KEY");
+    let dst = Connection::new(":memory:").unwrap();
+    let options = CopyOptions {
+        dst_table: Some("user_copy".to_string()),
+        ..Default::default()
+    };
+
+    let report = copy_table(&src, &dst, "user", &options).unwrap();
+    assert!(report.created);
+    assert_eq!(report.read, 3);
+    assert_eq!(report.written, 3);
+    assert!(report.watermark.is_none());
+
+    let count: u64 = dst
+        .value("SELECT COUNT(*) AS n FROM user_copy", "n", ())
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn watermark_upserts_changed_rows() {
+    let src = src(" PRIMARY KEY");
+    let dst = Connection::new(":memory:").unwrap();
+
+    let report = copy_table(&src, &dst, "user", &watermark()).unwrap();
+    assert_eq!(report.written, 3);
+    match report.watermark {
+        Some(Dypes::String(ref x)) => assert_eq!(x, "2024-01-03 00:00:00"),
+        ref x => panic!("{:?}", x),
+    }
+
+    src.execute(
+        "UPDATE user SET name = 'B', updated_at = '2024-01-04 00:00:00' WHERE id = 2",
+        (),
+    ).unwrap();
+    src.execute(
+        "INSERT INTO user (id, name, updated_at) VALUES (4, 'd', '2024-01-05 00:00:00')",
+        (),
+    ).unwrap();
+
+    // Rows at the highest copied watermark are read again
+    let report = copy_table(&src, &dst, "user", &watermark()).unwrap();
+    assert!(!report.created);
+    assert_eq!(report.read, 3);
+    assert_eq!(
+        names(&dst),
+        vec![
+            (1, "a".to_string()),
+            (2, "B".to_string()),
+            (3, "c".to_string()),
+            (4, "d".to_string()),
+        ]
+    );
+
+    let report = copy_table(&src, &dst, "user", &watermark()).unwrap();
+    assert_eq!(report.read, 1);
+    assert_eq!(names(&dst).len(), 4);
+}
+
+#[test]
+fn watermark_without_key_appends_newer_rows() {
+    let src = src("");
+    let dst = Connection::new(":memory:").unwrap();
+    copy_table(&src, &dst, "user", &watermark()).unwrap();
+
+    src.execute(
+        "INSERT INTO user (id, name, updated_at) VALUES (4, 'd', '2024-01-05 00:00:00')",
+        (),
+    ).unwrap();
+    let report = copy_table(&src, &dst, "user", &watermark()).unwrap();
+    assert_eq!(report.read, 1);
+    assert_eq!(names(&dst).len(), 4);
+
+    let options = CopyOptions {
+        watermark: Some("missing".to_string()),
+        ..Default::default()
+    };
+    assert!(copy_table(&src, &dst, "user", &options).is_err());
+}
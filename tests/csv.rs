@@ -0,0 +1,58 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, CsvOptions};
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute("CREATE TABLE user (id INTEGER NOT NULL, name TEXT, score REAL)", ())
+        .unwrap();
+    con
+}
+
+type User = (u64, Option<String>, Option<f64>);
+
+fn users(con: &Connection) -> Vec<User> {
+    con.array("SELECT id, name, score FROM user ORDER BY id", (), false)
+        .unwrap()
+}
+
+fn round_trip(options: &CsvOptions) -> String {
+    let src = con();
+    src.execute(
+        "INSERT INTO user (id, name, score) VALUES
+        (1, 'a;b,c \"d\"', 1.5), (2, '', NULL), (3, NULL, 2),
+        (4, 'multi' || char(10) || 'line', 0)",
+        (),
+    ).unwrap();
+
+    let mut out = Vec::new();
+    let rows = src
+        .export_csv("SELECT * FROM user ORDER BY id", (), &mut out, options)
+        .unwrap();
+    assert_eq!(rows, 4);
+
+    let dst = con();
+    let report = dst.import_csv("user", &out[..], options).unwrap();
+    assert_eq!(report.inserted, 4);
+    assert_eq!(users(&dst), users(&src));
+
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn round_trip_with_comma() {
+    let out = round_trip(&CsvOptions::default());
+    assert!(out.starts_with("id,name,score\n1,\"a;b,c \"\"d\"\"\",1.5\n"), "{}", out);
+}
+
+#[test]
+fn round_trip_with_semicolon() {
+    let options = CsvOptions {
+        delimiter: ';',
+        ..Default::default()
+    };
+    let out = round_trip(&options);
+    assert!(out.starts_with("id;name;score\n1;\"a;b,c \"\"d\"\"\";1.5\n"), "{}", out);
+    assert!(out.contains("\n2;\"\";\n3;;2"), "{}", out);
+}
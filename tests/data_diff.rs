@@ -0,0 +1,62 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{diff_tables, Connectionable, Dypes};
+
+/// A user table holding the keys a0..a999 and B0..B599, collated as given
+fn con(collate: &str) -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute(
+        &format!(
+            "CREATE TABLE user (name TEXT NOT NULL COLLATE {} PRIMARY KEY, age INTEGER)",
+            collate
+        ),
+        (),
+    ).unwrap();
+    con.execute(
+        "WITH RECURSIVE n(x) AS (SELECT 0 UNION ALL SELECT x + 1 FROM n WHERE x < 999)
+        INSERT INTO user (name, age) SELECT 'a' || x, x FROM n",
+        (),
+    ).unwrap();
+    con.execute(
+        "WITH RECURSIVE n(x) AS (SELECT 0 UNION ALL SELECT x + 1 FROM n WHERE x < 599)
+        INSERT INTO user (name, age) SELECT 'B' || x, x FROM n",
+        (),
+    ).unwrap();
+    con
+}
+
+#[test]
+fn same_rows_in_another_collation() {
+    let a = con("NOCASE");
+    let b = con("BINARY");
+
+    let diff = diff_tables(&a, &b, "user", &[]).unwrap();
+    assert!(diff.is_empty(), "{}", diff);
+    assert_eq!(diff.rows_a, 1600);
+    assert_eq!(diff.rows_b, 1600);
+}
+
+#[test]
+fn reports_inserted_deleted_and_changed() {
+    let a = con("NOCASE");
+    let b = con("BINARY");
+    b.execute("UPDATE user SET age = 1 WHERE name = 'B5'", ())
+        .unwrap();
+    b.execute("DELETE FROM user WHERE name = 'a7'", ()).unwrap();
+    b.execute("INSERT INTO user (name, age) VALUES ('c', 2)", ())
+        .unwrap();
+
+    let diff = diff_tables(&a, &b, "user", &[]).unwrap();
+    assert_eq!(diff.inserted.len(), 1);
+    assert_eq!(diff.deleted.len(), 1);
+    assert_eq!(diff.changed.len(), 1);
+
+    let change = &diff.changed[0];
+    match change.key[0] {
+        Dypes::String(ref x) => assert_eq!(x, "B5"),
+        ref x => panic!("{:?}", x),
+    }
+    assert_eq!(change.columns.len(), 1);
+    assert_eq!(change.columns[0].name, "age");
+}
@@ -0,0 +1,116 @@
+extern crate dengine;
+#[macro_use]
+extern crate dengine_derive;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, Error};
+
+#[derive(Debug, Queryable)]
+struct Counter {
+    id: u64,
+    hits: u32,
+    #[dengine(lossy)]
+    ratio: i64,
+}
+
+#[derive(Debug, Default, Queryable)]
+struct Address {
+    city: String,
+}
+
+#[derive(Debug, Default, Queryable, Insertable, Table)]
+#[dengine(table = "user")]
+struct User {
+    #[dengine(primary_key)]
+    id: u64,
+    #[dengine(rename = "user_name")]
+    name: String,
+    #[dengine(flatten, prefix = "home_")]
+    home: Address,
+    #[dengine(has_many = "counter", fk = "id")]
+    counters: Vec<Counter>,
+    #[dengine(skip)]
+    note: String,
+}
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute(
+        "CREATE TABLE counter (id INTEGER NOT NULL, hits INTEGER, ratio REAL)",
+        (),
+    ).unwrap();
+    con
+}
+
+#[test]
+fn converts_in_range_values() {
+    let con = con();
+    con.execute("INSERT INTO counter VALUES (1, 5, 2.5)", ())
+        .unwrap();
+
+    let res: Vec<Counter> = con.array("SELECT * FROM counter", (), false).unwrap();
+    assert_eq!(res[0].id, 1);
+    assert_eq!(res[0].hits, 5);
+    assert_eq!(res[0].ratio, 2);
+}
+
+#[test]
+fn out_of_range_value_is_err() {
+    let con = con();
+    con.execute("INSERT INTO counter VALUES (1, -1, 1.0)", ())
+        .unwrap();
+
+    match con.array::<Counter, _>("SELECT * FROM counter", (), false) {
+        Err(Error::ConversionErr(x)) => assert!(x.starts_with("hits"), "{}", x),
+        x => panic!("{:?}", x),
+    }
+
+    match con.row::<Counter, _>("SELECT * FROM counter", ()) {
+        Err(Error::ConversionErr(_)) => (),
+        x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+fn missing_column_is_err() {
+    let con = con();
+    con.execute("INSERT INTO counter VALUES (1, 2, 3.0)", ())
+        .unwrap();
+
+    let rt = con.for_each_row("SELECT id, hits FROM counter", (), |_x: Counter| Ok(()));
+    assert!(rt.is_err());
+}
+
+#[test]
+fn insert_leaves_out_relations_and_skipped_fields() {
+    let con = con();
+    con.create_table::<User>().unwrap();
+    con.execute("ALTER TABLE user ADD COLUMN home_city TEXT", ())
+        .unwrap();
+    con.execute("INSERT INTO counter VALUES (1, 5, 2.5)", ())
+        .unwrap();
+
+    let schema = con.schema().unwrap();
+    let columns: Vec<&str> = schema.table("user").unwrap()
+        .columns
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    assert_eq!(columns, vec!["id", "user_name", "home_city"]);
+
+    let user = User {
+        id: 1,
+        name: "a".to_string(),
+        note: "not stored".to_string(),
+        ..Default::default()
+    };
+    con.insert("user", vec![user]).unwrap();
+    con.execute("UPDATE user SET home_city = 'Kochi'", ()).unwrap();
+
+    let mut users: Vec<User> = con.array("SELECT * FROM user", (), false).unwrap();
+    con.load_has_many(&mut users).unwrap();
+    assert_eq!(users[0].name, "a");
+    assert_eq!(users[0].home.city, "Kochi");
+    assert_eq!(users[0].counters.len(), 1);
+    assert_eq!(users[0].note, "");
+}
@@ -0,0 +1,94 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, DumpOptions, MysqlDialect};
+
+type Post = (u64, u64, Option<String>, Option<f64>, Option<Vec<u8>>);
+
+fn src() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    let sql = [
+        "CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT 'a')",
+        "CREATE TABLE post (
+            id INTEGER PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES user (id),
+            title TEXT,
+            score REAL,
+            data BLOB
+        )",
+        "CREATE INDEX post_user ON post (user_id)",
+        "INSERT INTO user (id, name) VALUES (1, 'it''s'), (2, 'b')",
+        "INSERT INTO post VALUES (1, 1, 'a;b' || char(10) || 'c', 1.5, X'00FF'),
+            (2, 2, NULL, NULL, NULL), (3, 1, '', -2, X'')",
+    ];
+    for n in &sql {
+        con.execute(n, ()).unwrap();
+    }
+    con
+}
+
+fn posts(con: &Connection) -> Vec<Post> {
+    con.array("SELECT * FROM post ORDER BY id", (), false)
+        .unwrap()
+}
+
+fn dump(con: &Connection, options: &DumpOptions) -> String {
+    let mut out = Vec::new();
+    con.dump(&mut out, options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn restores_schema_and_rows() {
+    let src = src();
+    let options = DumpOptions {
+        batch_size: 2,
+        ..Default::default()
+    };
+    let sql = dump(&src, &options);
+
+    let dst = Connection::new(":memory:").unwrap();
+    assert!(dst.restore(sql.as_bytes()).unwrap() > 0);
+
+    assert_eq!(posts(&dst), posts(&src));
+    let names: Vec<String> = dst.array("SELECT name FROM user ORDER BY id", (), false)
+        .unwrap();
+    assert_eq!(names, vec!["it's", "b"]);
+
+    let diff = src.schema().unwrap().diff(&dst.schema().unwrap());
+    assert!(diff.is_empty(), "{}", diff);
+}
+
+#[test]
+fn drop_tables_restores_over_existing_tables() {
+    let src = src();
+    let options = DumpOptions {
+        drop_tables: true,
+        tables: vec!["user".to_string()],
+        ..Default::default()
+    };
+    let sql = dump(&src, &options);
+    assert!(!sql.contains("post"));
+
+    src.execute("DELETE FROM user WHERE id = 2", ()).unwrap();
+    src.execute("PRAGMA foreign_keys = OFF", ()).unwrap();
+    src.restore(sql.as_bytes()).unwrap();
+
+    let count: u64 = src
+        .value("SELECT COUNT(*) AS n FROM user", "n", ())
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn writes_for_another_dialect() {
+    let options = DumpOptions {
+        dialect: Some(&MysqlDialect),
+        data: false,
+        ..Default::default()
+    };
+    let sql = dump(&src(), &options);
+
+    assert!(sql.contains("CREATE TABLE `user`"), "{}", sql);
+    assert!(!sql.contains("INSERT"), "{}", sql);
+}
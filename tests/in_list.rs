@@ -0,0 +1,53 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, Dypes, In};
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute("CREATE TABLE user (id INTEGER NOT NULL)", ())
+        .unwrap();
+    con.execute("INSERT INTO user (id) VALUES (1), (2), (3)", ())
+        .unwrap();
+    con
+}
+
+#[test]
+fn expands_lists() {
+    let con = con();
+
+    let ids: Vec<u64> = con
+        .array(
+            "SELECT id FROM user WHERE id IN (?) AND id > ? ORDER BY id",
+            (In(vec![1, 3]), 0),
+            false,
+        ).unwrap();
+    assert_eq!(ids, vec![1, 3]);
+
+    let ids: Vec<u64> = con
+        .array("SELECT id FROM user WHERE id IN (?)", In(Vec::<u64>::new()), false)
+        .unwrap();
+    assert!(ids.is_empty());
+
+    con.execute("DELETE FROM user WHERE id NOT IN (?)", In(vec![2]))
+        .unwrap();
+    let count: u64 = con
+        .value("SELECT COUNT(*) AS n FROM user", "n", ())
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn nested_list_is_err() {
+    let con = con();
+    let nested = Dypes::List(vec![Dypes::List(vec![Dypes::Int(1)])]);
+
+    assert!(
+        con.execute("DELETE FROM user WHERE id IN (?)", nested.clone())
+            .is_err()
+    );
+    assert!(
+        con.array::<u64, _>("SELECT id FROM user WHERE id IN (?)", nested, false)
+            .is_err()
+    );
+}
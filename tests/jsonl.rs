@@ -0,0 +1,64 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, JsonlOptions};
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute(
+        "CREATE TABLE user (id INTEGER NOT NULL, name TEXT, score REAL)",
+        (),
+    ).unwrap();
+    con
+}
+
+type User = (u64, Option<String>, Option<f64>);
+
+fn users(con: &Connection) -> Vec<User> {
+    con.array("SELECT id, name, score FROM user ORDER BY id", (), false)
+        .unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let src = con();
+    src.execute(
+        "INSERT INTO user (id, name, score) VALUES
+        (1, 'a \"quoted\"' || char(10) || 'line', 1.5), (2, NULL, NULL)",
+        (),
+    ).unwrap();
+
+    let mut out = Vec::new();
+    let rows = src
+        .export_jsonl("SELECT * FROM user ORDER BY id", (), &mut out)
+        .unwrap();
+    assert_eq!(rows, 2);
+    assert_eq!(String::from_utf8_lossy(&out).lines().count(), 2);
+
+    let dst = con();
+    let report = dst
+        .import_jsonl("user", &out[..], &JsonlOptions::default())
+        .unwrap();
+    assert_eq!(report.inserted, 2);
+    assert!(report.errors.is_empty());
+    assert_eq!(users(&dst), users(&src));
+}
+
+#[test]
+fn skips_bad_lines() {
+    let input = "{\"id\": 1, \"name\": \"a\"}\nnot json\n{\"ID\": 3, \"Name\": \"c\"}\n";
+    let dst = con();
+
+    assert!(dst
+        .import_jsonl("user", input.as_bytes(), &JsonlOptions::default())
+        .is_err());
+
+    let options = JsonlOptions {
+        skip_errors: true,
+        ..Default::default()
+    };
+    let report = dst.import_jsonl("user", input.as_bytes(), &options).unwrap();
+    assert_eq!(report.inserted, 2);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].line, 2);
+}
@@ -0,0 +1,123 @@
+extern crate dengine;
+
+use dengine::migrations::{Migration, Migrator};
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, SqliteDialect};
+use std::fs;
+use std::path::PathBuf;
+
+/// Empty directory in the temp dir, unique per test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dengine_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn tables(con: &Connection) -> Vec<String> {
+    con.array(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE '\\_%' ESCAPE '\\' \
+         ORDER BY name",
+        (),
+        false,
+    ).unwrap()
+}
+
+fn migrator() -> Migrator {
+    Migrator::new(vec![
+        Migration::new(3, "add_post", "CREATE TABLE post (id INTEGER)").down("DROP TABLE post"),
+        Migration::new(1, "add_user", "CREATE TABLE user (id INTEGER, name TEXT)")
+            .down("DROP TABLE user"),
+        Migration::new(
+            2,
+            "add_log",
+            "CREATE TABLE log (name TEXT);
+            CREATE TRIGGER user_log AFTER INSERT ON user BEGIN
+                INSERT INTO log (name) VALUES (new.name);
+            END;",
+        ).down("DROP TRIGGER user_log; DROP TABLE log"),
+    ]).unwrap()
+}
+
+#[test]
+fn from_dir_orders_by_version() {
+    let dir = temp_dir("from_dir");
+    let files = [
+        ("0010_add_post.up.sql", "CREATE TABLE post (id INTEGER)"),
+        ("0002_add_user.up.sql", "CREATE TABLE user (id INT)"),
+        ("0002_add_user.up.sqlite.sql", "CREATE TABLE user (id INTEGER)"),
+        ("0002_add_user.down.sql", "DROP TABLE user"),
+        ("README.md", "not a migration"),
+    ];
+    for (name, sql) in &files {
+        fs::write(dir.join(name), sql).unwrap();
+    }
+
+    let migrator = Migrator::from_dir(&dir).unwrap();
+    let versions: Vec<(u64, &str)> = migrator
+        .migrations()
+        .iter()
+        .map(|x| (x.version, x.name.as_str()))
+        .collect();
+    assert_eq!(versions, vec![(2, "add_user"), (10, "add_post")]);
+
+    let user = &migrator.migrations()[0];
+    assert_eq!(user.up_sql(&SqliteDialect), Some("CREATE TABLE user (id INTEGER)"));
+    assert_eq!(user.down_sql(&SqliteDialect), Some("DROP TABLE user"));
+    assert_eq!(migrator.migrations()[1].down_sql(&SqliteDialect), None);
+
+    fs::write(dir.join("0003_bad.sql"), "").unwrap();
+    assert!(Migrator::from_dir(&dir).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn migrates_pending_versions() {
+    let con = Connection::new(":memory:").unwrap();
+    let migrator = migrator();
+
+    assert_eq!(migrator.migrate(&con).unwrap(), vec![1, 2, 3]);
+    assert_eq!(tables(&con), vec!["log", "post", "user"]);
+    assert!(migrator.migrate(&con).unwrap().is_empty());
+
+    con.execute("INSERT INTO user (id, name) VALUES (1, 'a')", ())
+        .unwrap();
+    let logged: Vec<String> = con.array("SELECT name FROM log", (), false).unwrap();
+    assert_eq!(logged, vec!["a"]);
+}
+
+#[test]
+fn verify_rejects_changed_checksum() {
+    let con = Connection::new(":memory:").unwrap();
+    migrator().migrate(&con).unwrap();
+
+    let changed = Migrator::new(vec![Migration::new(
+        1,
+        "add_user",
+        "CREATE TABLE user (id INTEGER)",
+    )]).unwrap();
+    assert!(changed.pending(&con).is_err());
+    assert!(changed.migrate(&con).is_err());
+    assert!(changed.rollback_to(&con, 0).is_err());
+}
+
+#[test]
+fn rollback_to_reverts_newest_first() {
+    let con = Connection::new(":memory:").unwrap();
+    let migrator = migrator();
+    migrator.migrate(&con).unwrap();
+
+    assert_eq!(migrator.rollback_to(&con, 1).unwrap(), vec![3, 2]);
+    assert_eq!(tables(&con), vec!["user"]);
+    let applied: Vec<u64> = migrator
+        .applied(&con)
+        .unwrap()
+        .iter()
+        .map(|x| x.version)
+        .collect();
+    assert_eq!(applied, vec![1]);
+
+    assert_eq!(migrator.migrate(&con).unwrap(), vec![2, 3]);
+    assert_eq!(migrator.rollback_to(&con, 0).unwrap(), vec![3, 2, 1]);
+    assert!(tables(&con).is_empty());
+}
@@ -0,0 +1,53 @@
+#[macro_use]
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::Connectionable;
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute("CREATE TABLE user (id INTEGER NOT NULL, name TEXT)", ())
+        .unwrap();
+    con.execute(
+        "INSERT INTO user (id, name) VALUES (?, ?), (?, ?)",
+        (1, "a", 2, "b"),
+    ).unwrap();
+    con
+}
+
+#[test]
+fn named_params() {
+    let con = con();
+
+    let ids: Vec<u64> = con
+        .array(
+            "SELECT id FROM user WHERE id = :id OR name = :name ORDER BY id",
+            named_params!{ "id" => 1, "name" => "b" },
+            false,
+        ).unwrap();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+fn numbered_params() {
+    let con = con();
+
+    let n: i64 = con.value("SELECT ?2 - ?1 AS n", "n", (10, 3)).unwrap();
+    assert_eq!(n, -7);
+
+    assert!(con.value::<i64, _>("SELECT ?3 AS n", "n", (1, 2)).is_err());
+}
+
+#[test]
+fn native_names_beside_positional_params() {
+    let con = con();
+
+    // :a is the first param, ? the second and the second :a is the first again
+    let n: i64 = con
+        .value("SELECT :a * 10 + ? + :a AS n", "n", (1, 2))
+        .unwrap();
+    assert_eq!(n, 13);
+
+    let n: i64 = con.value("SELECT $a + ? AS n", "n", (1, 2)).unwrap();
+    assert_eq!(n, 3);
+}
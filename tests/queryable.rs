@@ -0,0 +1,54 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::Connectionable;
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute("CREATE TABLE user (id INTEGER NOT NULL, name TEXT)", ())
+        .unwrap();
+    con.execute(
+        "INSERT INTO user (id, name) VALUES (1, 'a'), (2, NULL)",
+        (),
+    ).unwrap();
+    con
+}
+
+#[test]
+fn scalars_and_tuples() {
+    let con = con();
+
+    let ids: Vec<u64> = con
+        .array("SELECT id FROM user ORDER BY id", (), false)
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    let rows: Vec<(u64, Option<String>)> = con
+        .array("SELECT id, name FROM user ORDER BY id", (), false)
+        .unwrap();
+    assert_eq!(rows, vec![(1, Some("a".to_string())), (2, None)]);
+
+    let names: Vec<Option<String>> = con
+        .array("SELECT name FROM user ORDER BY id", (), false)
+        .unwrap();
+    assert_eq!(names, vec![Some("a".to_string()), None]);
+}
+
+#[test]
+fn null_scalar_is_err() {
+    let con = con();
+
+    assert!(con.array::<String, _>("SELECT name FROM user", (), false).is_err());
+    assert!(con.column::<i32, _>("SELECT name FROM user", "name", ()).is_err());
+    assert!(con.row::<String, _>("SELECT name FROM user WHERE id = 2", ()).is_err());
+}
+
+#[test]
+fn short_row_is_err() {
+    let con = con();
+
+    assert!(con
+        .array::<(u64, String, u64), _>("SELECT id, name FROM user", (), false)
+        .is_err());
+    assert!(con.row::<(u64, u64), _>("SELECT id FROM user", ()).is_err());
+}
@@ -0,0 +1,79 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, DynRow, Dypes, Queryable, Row};
+
+/// The ids of a user and of the post joined to it
+#[derive(Debug)]
+struct Ids(Option<u64>, Option<u64>, Option<u64>, usize);
+
+impl Queryable for Ids {
+    fn new(row: Row) -> Self {
+        Ids(
+            row.get_at(0),
+            row.get("user.id"),
+            row.get("post.id"),
+            row.len(),
+        )
+    }
+}
+
+fn con() -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    con.execute("CREATE TABLE user (id INTEGER NOT NULL, name TEXT)", ())
+        .unwrap();
+    con.execute("CREATE TABLE post (id INTEGER NOT NULL, user_id INTEGER)", ())
+        .unwrap();
+    con.execute("INSERT INTO user (id, name) VALUES (1, 'a')", ())
+        .unwrap();
+    con.execute("INSERT INTO post (id, user_id) VALUES (7, 1)", ())
+        .unwrap();
+    con
+}
+
+#[test]
+fn reads_by_index_and_table() {
+    let con = con();
+    let res: Vec<Ids> = con
+        .array(
+            "SELECT user.id, post.id FROM user JOIN post ON post.user_id = user.id",
+            (),
+            false,
+        ).unwrap();
+
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].0, Some(1));
+    assert_eq!(res[0].1, Some(1));
+    assert_eq!(res[0].2, Some(7));
+    assert_eq!(res[0].3, 2);
+}
+
+#[test]
+fn index_past_last_column_is_none() {
+    let con = con();
+    let res: Vec<DynRow> = con.array("SELECT id FROM user", (), false).unwrap();
+
+    match res[0].get(0) {
+        Some(Dypes::Int(1)) => (),
+        x => panic!("{:?}", x),
+    }
+    assert!(res[0].get(1).is_none());
+
+    let res: Vec<Ids> = con.array("SELECT name FROM user", (), false).unwrap();
+    assert_eq!(res[0].0, None);
+    assert_eq!(res[0].3, 1);
+}
+
+#[test]
+fn select_reports_column_meta() {
+    let con = con();
+    let res = con
+        .select::<DynRow, _>("SELECT id, name, 1 AS one FROM user WHERE id > 5", (), false)
+        .unwrap();
+
+    let names: Vec<&str> = res.columns.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name", "one"]);
+    assert_eq!(res.columns[0].decl_type, Some("INTEGER".to_string()));
+    assert_eq!(res.columns[1].table, Some("user".to_string()));
+    assert_eq!(res.columns[2].table, None);
+}
@@ -0,0 +1,57 @@
+extern crate dengine;
+
+use dengine::sqlite::Connection;
+use dengine::{Connectionable, SchemaChange};
+
+fn con(sql: &[&str]) -> Connection {
+    let con = Connection::new(":memory:").unwrap();
+    for n in sql {
+        con.execute(n, ()).unwrap();
+    }
+    con
+}
+
+#[test]
+fn applies_added_tables_and_colums() {
+    let a = con(&["CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT 'a')"]);
+    let b = con(&[
+        "CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT 'a', age INTEGER)",
+        "CREATE TABLE post (id INTEGER PRIMARY KEY, title TEXT)",
+        "CREATE INDEX post_title ON post (title)",
+    ]);
+
+    let diff = a.schema().unwrap().diff(&b.schema().unwrap());
+    assert_eq!(diff.changes.len(), 2, "{}", diff);
+
+    for n in diff.alter_sql(Connection::dialect()).unwrap() {
+        a.execute(&n, ()).unwrap();
+    }
+    let diff = a.schema().unwrap().diff(&b.schema().unwrap());
+    assert!(diff.is_empty(), "{}", diff);
+}
+
+#[test]
+fn quoted_defaults_compare_as_written() {
+    let a = con(&["CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT DEFAULT 'a', note TEXT)"]);
+    let b = con(&[
+        "CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT DEFAULT 'A', \
+         note TEXT DEFAULT 'NULL')",
+    ]);
+
+    let diff = a.schema().unwrap().diff(&b.schema().unwrap());
+    let altered: Vec<&str> = diff
+        .changes
+        .iter()
+        .filter_map(|x| match x {
+            SchemaChange::AlterColumn(_, _, c) => Some(c.name.as_str()),
+            _ => None,
+        }).collect();
+    assert_eq!(altered, vec!["name", "note"]);
+
+    let c = con(&[
+        "CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT DEFAULT ('a'), \
+         note TEXT DEFAULT NULL)",
+    ]);
+    let diff = a.schema().unwrap().diff(&c.schema().unwrap());
+    assert!(diff.is_empty(), "{}", diff);
+}